@@ -0,0 +1,28 @@
+//! How to exit with `sysexits.h` codes on argument errors.
+//!
+//! Running this example without arguments prints this message and
+//! exits with code 64 (`EX_USAGE`):
+//! -----------------------------------------------------
+//! error: The following required arguments were not provided:
+//!     --count <count>
+//!
+//! USAGE:
+//!     exit_code --count <count>
+//!
+//! For more information try --help
+//! -----------------------------------------------------
+
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "exit_code", exit_code = structopt::sysexits)]
+struct Opt {
+    /// Number of items
+    #[structopt(short, long)]
+    count: u32,
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    println!("{:?}", opt);
+}
@@ -0,0 +1,44 @@
+//! How to use `#[structopt::main]` to parse arguments and report errors.
+//!
+//! Running this example with `--retries 0` prints this message and
+//! exits with code 75:
+//! -----------------------------------------------------
+//! error: giving up after 0 retries
+//! -----------------------------------------------------
+
+use std::fmt;
+
+use structopt::{MainError, StructOpt};
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "main_attr")]
+struct Opt {
+    /// Number of retries
+    #[structopt(short, long, default_value = "3")]
+    retries: u32,
+}
+
+#[derive(Debug)]
+struct GiveUp(u32);
+
+impl fmt::Display for GiveUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "giving up after {} retries", self.0)
+    }
+}
+
+impl MainError for GiveUp {
+    fn exit_code(&self) -> i32 {
+        // EX_TEMPFAIL
+        75
+    }
+}
+
+#[structopt::main]
+fn main(opt: Opt) -> Result<(), GiveUp> {
+    if opt.retries == 0 {
+        return Err(GiveUp(opt.retries));
+    }
+    println!("{:?}", opt);
+    Ok(())
+}
@@ -0,0 +1,114 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Loading of `.env` files, with the `dotenv` feature.
+//!
+//! The variables of a `.env` file are given to the arguments having an
+//! [`env`](crate#environment-variable-fallback) attribute, without touching the
+//! environment of the process. The value of an argument comes, in order of
+//! precedence, from:
+//!
+//! 1. the command line,
+//! 2. the environment of the process,
+//! 3. the `.env` file,
+//! 4. its `default_value`.
+//!
+//! [`StructOpt::from_args`](crate::StructOpt::from_args) and
+//! [`StructOpt::from_args_safe`](crate::StructOpt::from_args_safe) read the
+//! `.env` file of the current directory, or the one given with
+//! `#[structopt(dotenv = "path")]` on top of the struct or enum. A missing file
+//! is ignored. The other constructors don't read any file, but [`apply`] does
+//! the same for any [`App`].
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! # let dir = std::env::temp_dir().join("structopt-dotenv-doc");
+//! # std::fs::create_dir_all(&dir).unwrap();
+//! # let path = dir.join(".env");
+//! std::fs::write(&path, "DOC_LOG_LEVEL=debug\n").unwrap();
+//!
+//! #[derive(StructOpt, Debug, PartialEq)]
+//! struct Opt {
+//!     #[structopt(long, env = "DOC_LOG_LEVEL", default_value = "info")]
+//!     log_level: String,
+//! }
+//!
+//! let app = structopt::dotenv::apply(Opt::clap(), &path).unwrap();
+//! let opt = Opt::from_clap(&app.get_matches_from(&["test"]));
+//! assert_eq!(opt.log_level, "debug");
+//! assert!(std::env::var_os("DOC_LOG_LEVEL").is_none());
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::clap::{App, Error, ErrorKind};
+
+/// Gives the variables of the `.env` file at `path` to the arguments of `app`
+/// and of its subcommands that have an environment variable missing from the
+/// environment of the process.
+///
+/// A missing file leaves `app` unchanged. Returns an error of kind
+/// [`ErrorKind::Io`] if the file can't be read, or [`ErrorKind::Format`] if
+/// one of its lines can't be parsed.
+pub fn apply<'a, 'b, P: AsRef<Path>>(app: App<'a, 'b>, path: P) -> Result<App<'a, 'b>, Error> {
+    let path = path.as_ref();
+    let iter = match dotenv_dep::from_path_iter(path) {
+        Ok(iter) => iter,
+        Err(err) if err.not_found() => return Ok(app),
+        Err(err) => return Err(dotenv_error(path, &err, ErrorKind::Io)),
+    };
+
+    let mut vars = HashMap::new();
+    for var in iter {
+        let (key, value) = var.map_err(|err| dotenv_error(path, &err, ErrorKind::Format))?;
+        vars.insert(key, value);
+    }
+
+    let mut app = app;
+    set_env(&mut app, &vars);
+    Ok(app)
+}
+
+fn dotenv_error(path: &Path, err: &dotenv_dep::Error, kind: ErrorKind) -> Error {
+    Error::with_description(&format!("Can't load '{}': {}", path.display(), err), kind)
+}
+
+// clap 2 reads the environment when `env` is called on an `Arg`, so we fill in
+// the values it didn't find, through the fields of its parser which are public
+// but hidden. Flags can't have an environment variable.
+fn set_env(app: &mut App<'_, '_>, vars: &HashMap<String, String>) {
+    let fill = |env: &mut Option<(&std::ffi::OsStr, Option<std::ffi::OsString>)>| {
+        if let Some((name, value @ None)) = env {
+            *value = name
+                .to_str()
+                .and_then(|name| vars.get(name))
+                .map(Into::into);
+        }
+    };
+
+    for opt in &mut app.p.opts {
+        fill(&mut opt.v.env);
+    }
+
+    // the map of positionals has no `values_mut` without clap's `vec_map`
+    // feature, but all of its entries are occupied
+    let indexes: Vec<_> = app.p.positionals.values().map(|p| p.index).collect();
+    for index in indexes {
+        let pos = app
+            .p
+            .positionals
+            .entry(index as usize)
+            .or_insert_with(|| unreachable!());
+        fill(&mut pos.v.env);
+    }
+
+    for sub in &mut app.p.subcommands {
+        set_env(sub, vars);
+    }
+}
@@ -41,6 +41,10 @@
 //! paw = "1.0"
 //! ```
 //!
+//! Loading environment variables from a `.env` file is disabled by default,
+//! but can be enabled with the feature `dotenv` (which needs Rust 1.56), see
+//! [Loading `.env` files](#loading-env-files).
+//!
 //! # Table of Contents
 //!
 //! - [How to `derive(StructOpt)`](#how-to-derivestructopt)
@@ -56,11 +60,23 @@
 //! - [Skipping fields](#skipping-fields)
 //! - [Subcommands](#subcommands)
 //!     - [Optional subcommands](#optional-subcommands)
+//!     - [Default subcommand](#default-subcommand)
+//!     - [Multicall binaries](#multicall-binaries)
+//!     - [Running subcommands](#running-subcommands)
 //!     - [External subcommands](#external-subcommands)
 //!     - [Flattening subcommands](#flattening-subcommands)
 //! - [Flattening](#flattening)
+//! - [Tuple and newtype structs](#tuple-and-newtype-structs)
 //! - [Custom string parsers](#custom-string-parsers)
+//! - [Range and length constraints](#range-and-length-constraints)
+//! - [Cross-field validation](#cross-field-validation)
 //! - [Generics](#generics)
+//! - [Parsing a command line string](#parsing-a-command-line-string)
+//! - [Updating an existing value](#updating-an-existing-value)
+//! - [Building from a map](#building-from-a-map)
+//! - [Exit codes](#exit-codes)
+//! - [Machine-readable errors](#machine-readable-errors)
+//! - [`#[structopt::main]`](#structoptmain)
 //!
 //!
 //!
@@ -209,6 +225,28 @@
 //! As long as `method_name` is not one of the magical methods -
 //! it will be translated into a mere method call.
 //!
+//! The methods relating arguments to each other - `requires`, `conflicts_with`,
+//! `required_unless` and their `_all`/`_one` variants - also accept the
+//! identifiers of sibling fields instead of argument names. They are resolved
+//! at compile time to the names of the arguments, after `rename_all`. Any other
+//! expression, like a string or a constant, is passed to clap unchanged.
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(StructOpt, Debug)]
+//! #[structopt(rename_all = "screaming-snake")]
+//! struct Opt {
+//!     #[structopt(long, conflicts_with = output_dir)]
+//!     output_file: Option<String>,
+//!     #[structopt(long, requires = format)]
+//!     output_dir: Option<String>,
+//!     #[structopt(long)]
+//!     format: Option<String>,
+//! }
+//!
+//! assert!(Opt::from_iter_safe(&["test", "--OUTPUT_FILE", "a", "--OUTPUT_DIR", "b"]).is_err());
+//! ```
+//!
 //! **Note:**
 //! _________________
 //!
@@ -294,6 +332,10 @@
 //!
 //!     Usable only on field-level.
 //!
+//! - [`auto_short`](#specifying-argument-types): `auto_short`
+//!
+//!     Usable only on top of a struct, an enum or an enum variant.
+//!
 //! - [`long`](#specifying-argument-types): `long [= "long-opt-name"]`
 //!
 //!     Usable only on field-level.
@@ -307,7 +349,7 @@
 //!
 //!     Usable both on top level and field level.
 //!
-//! - [`parse`](#custom-string-parsers): `parse(type [= path::to::parser::fn])`
+//! - [`parse`](#custom-string-parsers): `parse(type [= parser] [, context])`
 //!
 //!     Usable only on field-level.
 //!
@@ -327,10 +369,58 @@
 //!
 //!     Usable only on enum variants.
 //!
+//! - [`default_subcommand`](#default-subcommand)
+//!
+//!     Usable only on enum variants.
+//!
+//! - [`multicall`](#multicall-binaries)
+//!
+//!     Usable only on top of an enum.
+//!
+//! - [`run`](#running-subcommands): `run [(context = Type, output = Type)]` / `run = handler`
+//!
+//!     The first form is usable only on top of an enum, the second only on enum variants.
+//!
+//! - [`range`](#range-and-length-constraints): `range = expr`
+//!
+//!     Usable only on arguments taking a value.
+//!
+//! - [`len`](#range-and-length-constraints): `len = expr`
+//!
+//!     Usable only on arguments taking a value.
+//!
+//! - [`validate`](#cross-field-validation): `validate = path` / `validate = closure`
+//!
+//!     Usable only on top of a struct, an enum or an enum variant.
+//!
+//! - [`exit_code`](#exit-codes): `exit_code = expr`
+//!
+//!     Usable only on top of a struct or an enum.
+//!
+//! - [`error_format`](#machine-readable-errors): `error_format = "json"/"text"`
+//!
+//!     Usable only on top of a struct or an enum.
+//!
+//! - [`dotenv`](#loading-env-files): `dotenv = "path"`
+//!
+//!     Usable only on top of a struct or an enum, with the `dotenv` feature.
+//!
+//! - [`sensitive`](#sensitive-arguments): `sensitive`
+//!
+//!     Usable only on fields.
+//!
+//! - [`env_file`](#secrets-in-files): `env_file`
+//!
+//!     Usable only on fields having an environment variable.
+//!
 //! - [`env`](#environment-variable-fallback): `env [= str_literal]`
 //!
 //!     Usable only on field-level.
 //!
+//! - [`env_prefix`](#configuring-from-the-environment-only): `env_prefix = "prefix"`
+//!
+//!     Usable only on top of a struct, an enum or an enum variant.
+//!
 //! - [`rename_all_env`](#auto-deriving-environment-variables):
 //!     [`rename_all_env = "kebab"/"snake"/"screaming-snake"/"camel"/"pascal"/"verbatim"/"lower"/"upper"]`
 //!
@@ -462,6 +552,34 @@
 //! #    &["test", "--foo-option", "", "-b", "", "--baz", "", "--custom", "", "positional"]);
 //! ```
 //!
+//! With `#[structopt(auto_short)]` on top of a struct, an enum or an enum
+//! variant, every argument having a `long` flag but no `short` one gets a short
+//! flag: the first letter of its long flag which isn't used yet, or else the
+//! first one whose other case isn't. The fields are handled in order, after the
+//! explicit `short` flags, `-h` and `-V`, so adding a field can change the
+//! short flags of the ones after it: set the short flags you want to keep
+//! explicitly. The short flags of a `flatten`ed struct are only known at
+//! runtime, so `flatten` can't be used along with `auto_short`. A struct with
+//! `auto_short` can itself be flattened, but its choices don't know the flags
+//! of the struct around it: [`StructOpt::debug_assert`] checks them.
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(StructOpt, Debug)]
+//! #[structopt(auto_short)]
+//! struct Opt {
+//!     #[structopt(long)] // -v
+//!     verbose: bool,
+//!     #[structopt(long)] // -e, since -v is used
+//!     version_file: Option<String>,
+//!     #[structopt(long)] // -o, since -h is used by --help
+//!     host: Option<String>,
+//! }
+//!
+//! let opt = Opt::from_iter(&["test", "-v", "-o", "localhost"]);
+//! assert_eq!(opt.host.as_deref(), Some("localhost"));
+//! ```
+//!
 //! ## Default values
 //!
 //! In clap, default values for options can be specified via [`Arg::default_value`].
@@ -715,6 +833,49 @@
 //! }
 //! ```
 //!
+//! ### Secrets in files
+//!
+//! Following the convention of Docker secrets, `#[structopt(env_file)]` takes
+//! the value of an argument from the file named by its environment variable
+//! suffixed with `_FILE`, when the variable itself isn't set. Blanks around the
+//! content of the file are trimmed. If the file can't be read while the value
+//! would come from it, parsing fails with a
+//! [`clap::ErrorKind::ValueValidation`] error, or with a
+//! [`clap::ErrorKind::MissingRequiredArgument`] one if the argument is
+//! required. Such arguments are always [sensitive](#sensitive-arguments), so
+//! the content of the file isn't shown in the help message.
+//!
+//! ### Sensitive arguments
+//!
+//! The value of an argument marked with `#[structopt(sensitive)]`, like a
+//! password or a token, isn't printed by structopt:
+//!
+//! - the help message shows neither its default value nor the value of its
+//!   environment variable, as with `hide_default_value` and `hide_env_values`,
+//! - the errors of its parser, of `range` and `len` are replaced by a fixed
+//!   message, since they may contain the value, and the value is replaced by
+//!   `***` in the [`ErrorReport`] of
+//!   [machine-readable errors](#machine-readable-errors),
+//! - `possible_values`, whose errors are printed by clap with the value, is
+//!   rejected at compile time.
+//!
+//! `sensitive` doesn't change the struct itself: `#[derive(Debug)]` prints the
+//! field in clear, unless its type is [`Secret<T>`](Secret), whose `Debug`
+//! writes `***`. Arguments of type `Secret<T>` are always sensitive, so use
+//! it for values that must not end up in logs.
+//!
+//! ```
+//! # use structopt::{Secret, StructOpt};
+//! #[derive(StructOpt, Debug)]
+//! struct Opt {
+//!     /// Read from $DB_PASSWORD, or from the file named by $DB_PASSWORD_FILE
+//!     #[structopt(long, env = "DB_PASSWORD", env_file)]
+//!     db_password: Secret<String>,
+//! }
+//! # let opt = Opt::from_iter(&["test", "--db-password", "hunter2"]);
+//! # assert_eq!(format!("{:?}", opt), "Opt { db_password: *** }");
+//! ```
+//!
 //! ### Auto-deriving environment variables
 //!
 //! Environment variables tend to be called after the corresponding `struct`'s field,
@@ -741,6 +902,67 @@
 //! `rename_all_env` works exactly as `rename_all` (including overriding)
 //! except default casing is `SCREAMING_SNAKE_CASE` instead of `kebab-case`.
 //!
+//! ### Loading `.env` files
+//!
+//! With the `dotenv` feature, [`StructOpt::from_args`] and
+//! [`StructOpt::from_args_safe`] also look for the environment variables of
+//! the arguments in the `.env` file of the current directory, if any. The
+//! environment of the process isn't modified, and takes precedence over the
+//! file: the value of an argument comes from the command line, then the
+//! environment, then the `.env` file, then its `default_value`.
+//!
+//! Another file can be given with `#[structopt(dotenv = "path")]` on top of the
+//! struct or enum. Other constructors, such as [`StructOpt::from_iter`], never
+//! read a file, but `structopt::dotenv::apply` gives the variables of any file
+//! to an [`App`](clap::App).
+//!
+//! ```ignore
+//! #[derive(StructOpt)]
+//! #[structopt(dotenv = "config/dev.env")]
+//! struct Opt {
+//!     #[structopt(long, env = "DATABASE_URL")]
+//!     database_url: String,
+//! }
+//! ```
+//!
+//! ### Configuring from the environment only
+//!
+//! With `#[structopt(env_prefix = "APP_")]` on top of a struct, an enum or an
+//! enum variant, every argument gets an environment variable made of the prefix
+//! and its name cased by `rename_all_env`, as if each had `env`. Explicit
+//! `env` attributes get the prefix too, unless they set the name of the
+//! variable. Flattened structs need their own `env_prefix`.
+//!
+//! Flags, including `parse(from_occurrences)` and `parse(from_flag)`, read
+//! their variable when they aren't given on the command line: `true`, `yes`
+//! or `on` set them, `false`, `no`, `off` or an empty value don't, and a
+//! number gives the number of occurrences. As clap 2 can't attach a variable
+//! to a flag, it isn't shown in the help message.
+//!
+//! [`StructOpt::from_env`] then builds the struct from the environment alone,
+//! without looking at the command line.
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(StructOpt, Debug, PartialEq)]
+//! #[structopt(env_prefix = "MY_SERVICE_")]
+//! struct Config {
+//!     #[structopt(long)]
+//!     port: u16,
+//!     #[structopt(long, default_value = "info")]
+//!     log_level: String,
+//! }
+//!
+//! std::env::set_var("MY_SERVICE_PORT", "8080");
+//! assert_eq!(
+//!     Config::from_env(),
+//!     Config {
+//!         port: 8080,
+//!         log_level: "info".to_string()
+//!     }
+//! );
+//! ```
+//!
 //! ## Skipping fields
 //!
 //! Sometimes you may want to add a field to your `Opt` struct that is not
@@ -880,6 +1102,29 @@
 //! + `make-cookie sparkle -mmm --color "green"`
 //! + `make-cookie finish 130 glaze 3`
 //!
+//! Tuple variants with several fields describe simple subcommands taking
+//! positional arguments in the order of the fields. The fields are named
+//! `arg0`, `arg1`, ... unless a `name` is given, and accept the same
+//! attributes and doc comments as struct fields:
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! # use std::path::PathBuf;
+//! #[derive(StructOpt)]
+//! enum Command {
+//!     /// Copy a file
+//!     Copy(
+//!         /// The file to copy
+//!         #[structopt(name = "SOURCE")]
+//!         PathBuf,
+//!         /// Where to copy it
+//!         #[structopt(name = "DEST")]
+//!         PathBuf,
+//!     ),
+//!     Rename(String, String),
+//! }
+//! ```
+//!
 //! ### Optional subcommands
 //!
 //! Subcommands may be optional:
@@ -901,6 +1146,131 @@
 //! }
 //! ```
 //!
+//! ### Default subcommand
+//!
+//! One variant of a subcommand enum may be marked with `default_subcommand`.
+//! When no subcommand is given on the command line, this variant is used and
+//! its arguments are parsed from the parent command, so `tool --release`
+//! behaves like `tool run --release`:
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(Debug, PartialEq, StructOpt)]
+//! struct Opt {
+//!     #[structopt(subcommand)]
+//!     cmd: Command,
+//! }
+//!
+//! #[derive(Debug, PartialEq, StructOpt)]
+//! enum Command {
+//!     #[structopt(default_subcommand)]
+//!     Run {
+//!         #[structopt(long)]
+//!         release: bool,
+//!     },
+//!     Build,
+//! }
+//!
+//! assert_eq!(
+//!     Opt::from_iter(&["tool", "--release"]),
+//!     Opt { cmd: Command::Run { release: true } },
+//! );
+//! assert_eq!(
+//!     Opt::from_iter(&["tool", "build"]),
+//!     Opt { cmd: Command::Build },
+//! );
+//! ```
+//!
+//! The arguments of the default variant are added to the parent command, and
+//! [`AppSettings::SubcommandsNegateReqs`] is set on it: required arguments of
+//! the parent are not enforced when a subcommand is given explicitly.
+//!
+//! [`AppSettings::SubcommandsNegateReqs`]: https://docs.rs/clap/2/clap/enum.AppSettings.html#variant.SubcommandsNegateReqs
+//!
+//! ### Multicall binaries
+//!
+//! A busybox-style binary installed under several names (usually via symlinks)
+//! can dispatch on the name it was invoked by. Mark the subcommand enum with
+//! `multicall` and [`StructOpt::from_args`] will pick the subcommand matching
+//! the basename of `argv[0]`, honoring `rename_all` and subcommand aliases.
+//! When invoked by any other name, the subcommand is parsed as usual:
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(Debug, PartialEq, StructOpt)]
+//! #[structopt(multicall)]
+//! enum Applet {
+//!     #[structopt(alias = "dir")]
+//!     Ls {
+//!         #[structopt(short)]
+//!         all: bool,
+//!     },
+//!     Cat {
+//!         file: String,
+//!     },
+//! }
+//!
+//! assert_eq!(Applet::from_iter(&["/bin/ls", "-a"]), Applet::Ls { all: true });
+//! assert_eq!(Applet::from_iter(&["dir"]), Applet::Ls { all: false });
+//! assert_eq!(
+//!     Applet::from_iter(&["busybox", "cat", "foo"]),
+//!     Applet::Cat { file: "foo".into() },
+//! );
+//! ```
+//!
+//! ### Running subcommands
+//!
+//! Instead of matching by hand on the parsed subcommand, an enum marked with
+//! `run(context = C, output = R)` implements [`StructOptRun<C>`], whose `run`
+//! method calls the handler of the parsed variant:
+//!
+//! - a variant marked with `run = handler` calls `handler(fields..., &C) -> R`,
+//!   passing the fields of the variant in declaration order;
+//! - an unmarked single-typed tuple variant calls [`StructOptRun::run`] on
+//!   its payload, which must implement `StructOptRun<C, Output = R>`.
+//!
+//! Any other variant without a handler is a compile error, so a new variant
+//! can't be forgotten. Both `context` and `output` default to `()`.
+//!
+//! ```
+//! # use structopt::{StructOpt, StructOptRun};
+//! struct Repo;
+//!
+//! #[derive(StructOpt)]
+//! #[structopt(run(context = Repo, output = Result<(), String>))]
+//! enum Git {
+//!     #[structopt(run = add)]
+//!     Add { files: Vec<String> },
+//!     #[structopt(run = |_repo: &Repo| Ok(()))]
+//!     Status,
+//!     Commit(Commit),
+//! }
+//!
+//! fn add(files: Vec<String>, _repo: &Repo) -> Result<(), String> {
+//!     Ok(())
+//! }
+//!
+//! #[derive(StructOpt)]
+//! struct Commit {
+//!     #[structopt(short)]
+//!     message: String,
+//! }
+//!
+//! impl StructOptRun<Repo> for Commit {
+//!     type Output = Result<(), String>;
+//!
+//!     fn run(self, _repo: &Repo) -> Self::Output {
+//!         Ok(())
+//!     }
+//! }
+//!
+//! # /*
+//! let git = Git::from_args();
+//! # */
+//! # let git = Git::from_iter(&["git", "add", "README.md"]);
+//! git.run(&Repo).unwrap();
+//! ```
+//!
 //! ### External subcommands
 //!
 //! Sometimes you want to support not only the set of well-known subcommands
@@ -1019,6 +1389,56 @@
 //! library, parse the corresponding arguments in the main argument parser, and
 //! pass off this struct to a handler provided by that library.
 //!
+//! Two fields of a struct with the same short or long flag are a compile error.
+//! A field may take the `-h` of `--help` or the `-V` of `--version`: clap then
+//! drops the short flag of the built-in argument, which keeps its long one.
+//! The flags of a flattened struct can only be compared to the others at
+//! runtime: [`StructOpt::debug_assert`] checks the whole tree of commands, and
+//! is meant to be called from a test.
+//!
+//! ## Tuple and newtype structs
+//!
+//! The fields of a tuple struct are positional arguments, in declaration order.
+//! As for [tuple variants](#subcommands), they are named `arg0`, `arg1`, ...
+//! unless a `name` is given:
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(StructOpt)]
+//! struct Copy(
+//!     /// The file to copy
+//!     #[structopt(name = "SOURCE")]
+//!     String,
+//!     /// Where to copy it
+//!     #[structopt(name = "DEST")]
+//!     String,
+//! );
+//! ```
+//!
+//! A tuple struct whose single field is marked with `flatten` is a newtype: it
+//! delegates everything to the `StructOpt` implementation of the wrapped type,
+//! including its exit code, error format and `multicall` setting, so no other
+//! `structopt` attributes are allowed on it. This is handy to implement
+//! additional traits on a wrapper without declaring the arguments again. The
+//! `flatten` is required: without it, `struct Cli(Opt)` is a tuple struct
+//! whose single field is a positional argument parsed with `FromStr`, as
+//! before newtypes were supported.
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(StructOpt)]
+//! struct Opt {
+//!     #[structopt(short)]
+//!     verbose: bool,
+//! }
+//!
+//! #[derive(StructOpt)]
+//! struct Cli(#[structopt(flatten)] Opt);
+//!
+//! let Cli(opt) = Cli::from_iter(&["test", "-v"]);
+//! assert!(opt.verbose);
+//! ```
+//!
 //! ## Custom string parsers
 //!
 //! If the field type does not have a `FromStr` implementation, or you would
@@ -1054,6 +1474,20 @@
 //! | `from_occurrences`| `fn(u64) -> T`                        | `value as T`                    |
 //! | `from_flag`       | `fn(bool) -> T`                       | `::std::convert::From::from`    |
 //!
+//! Fields of type `PathBuf` or `OsString`, possibly in an `Option` or a `Vec`,
+//! use `parse(from_os_str)` by default, so that paths don't have to be valid
+//! UTF-8. Give them another parser, like `parse(try_from_str)`, to opt out.
+//! The types are recognized by their path, which must be the name alone, or
+//! start with `path::`, `ffi::`, `std::path::` or `std::ffi::`: a type of
+//! your own named `PathBuf` keeps using `FromStr`.
+//!
+//! Likewise, fields of type [`Duration`](std::time::Duration), named
+//! `Duration`, `time::Duration`, `std::time::Duration` or
+//! `core::time::Duration`, use [`parsers::duration`] by default, reading
+//! values like `1h30m`. The [`parsers`] module has parsers for other values
+//! found in nearly every command line: byte sizes, integers with a radix
+//! prefix and lists of ranges.
+//!
 //! The `from_occurrences` parser is special. Using `parse(from_occurrences)`
 //! results in the _number of flags occurrences_ being stored in the relevant
 //! field or being passed to the supplied function. In other words, it converts
@@ -1077,6 +1511,123 @@
 //! once to validate, and once to parse. Hence, make sure the function is
 //! side-effect-free.
 //!
+//! The parser may be any expression, not only a function path: a closure,
+//! or for the `try_from_*` variants any [`ValueParser`], such as a parser
+//! configured when it's built. A constant or unit struct implementing
+//! `ValueParser` must be given by reference, as `&HEX`, since a path alone is
+//! called as a function.
+//!
+//! With `parse(try_from_str = ..., context)`, the parser is a
+//! [`ContextParser`]: it also gets a [`ParseContext`] giving access to the
+//! values of the other arguments, by their names. These parsers are run by
+//! [`StructOpt::check_matches`] once the whole command line is parsed, and
+//! their errors are reported the same way. [`StructOpt::from_clap`] panics on
+//! a value they reject, so use [`StructOpt::try_from_clap`] to build a struct
+//! from matches of your own.
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! use structopt::ParseContext;
+//!
+//! fn scaled(src: &str, context: &ParseContext) -> Result<u64, String> {
+//!     let value: u64 = src.parse().map_err(|e| format!("{}", e))?;
+//!     match context.value_of("unit") {
+//!         Some("k") => Ok(value * 1000),
+//!         Some(unit) => Err(format!("unknown unit `{}`", unit)),
+//!         None => Ok(value),
+//!     }
+//! }
+//!
+//! #[derive(StructOpt)]
+//! struct Opt {
+//!     #[structopt(long, parse(try_from_str = |s| u8::from_str_radix(s, 16)))]
+//!     mask: u8,
+//!     #[structopt(long, parse(try_from_str = scaled, context))]
+//!     size: u64,
+//!     #[structopt(long)]
+//!     unit: Option<String>,
+//! }
+//!
+//! let opt = Opt::from_iter(&["test", "--mask", "ff", "--size", "2", "--unit", "k"]);
+//! assert_eq!(opt.mask, 255);
+//! assert_eq!(opt.size, 2000);
+//! ```
+//!
+//! When the type of a field doesn't implement the trait used by the default
+//! function of its parser, like `FromStr` for a field without `parse(...)`, the
+//! compile error points at the type, and since Rust 1.78 it tells which trait
+//! is missing and suggests giving a parser.
+//!
+//! ## Range and length constraints
+//!
+//! `#[structopt(range = expr)]` checks that the parsed value of an argument is
+//! in a range, and `#[structopt(len = expr)]` checks the length of the value,
+//! in characters. Any range expression can be used, including half-open ones
+//! and ones built from constants, like `range = 1..=MAX_JOBS` or `len = ..=8`.
+//! The constraints are shown in the help message, and a value outside of them
+//! is a [`clap::ErrorKind::ValueValidation`] error.
+//!
+//! On a `Vec` field, `range` checks each value and `len` checks the number of
+//! values. Its bounds become the `min_values` and `max_values` of the argument,
+//! so clap reports [`clap::ErrorKind::TooFewValues`] and
+//! [`clap::ErrorKind::TooManyValues`] errors, and a missing argument whose
+//! `len` doesn't accept zero values gives a
+//! [`clap::ErrorKind::WrongNumberOfValues`] error.
+//!
+//! ```
+//! # use structopt::{clap::ErrorKind, StructOpt};
+//! #[derive(StructOpt, Debug)]
+//! struct Opt {
+//!     /// The port to listen on
+//!     #[structopt(long, range = 1..=65535)]
+//!     port: u32,
+//!     #[structopt(long, len = 1..=8)]
+//!     name: String,
+//! }
+//!
+//! let err = Opt::from_iter_safe(&["test", "--port", "0", "--name", "a"]).unwrap_err();
+//! assert_eq!(err.kind, ErrorKind::ValueValidation);
+//! let err = Opt::from_iter_safe(&["test", "--port", "80", "--name", ""]).unwrap_err();
+//! assert_eq!(err.kind, ErrorKind::ValueValidation);
+//! ```
+//!
+//! ## Cross-field validation
+//!
+//! Constraints between several arguments can be checked with
+//! `#[structopt(validate = function)]` on top of a struct, an enum or an enum
+//! variant. Once the value is built, the function is called with it, as
+//! `fn(&Self) -> Result<(), String>` (for a variant, `Self` is the enum), and
+//! an error message becomes a [`clap::ErrorKind::ValueValidation`] error.
+//!
+//! Flattened structs and subcommands are checked before the struct containing
+//! them. All the constructors of [`StructOpt`] run the checks, through
+//! [`StructOpt::validate`], except [`StructOpt::from_clap`].
+//!
+//! ```
+//! # use structopt::{clap::ErrorKind, StructOpt};
+//! #[derive(StructOpt, Debug)]
+//! #[structopt(validate = Range::check)]
+//! struct Range {
+//!     #[structopt(long)]
+//!     start: u32,
+//!     #[structopt(long)]
+//!     end: u32,
+//! }
+//!
+//! impl Range {
+//!     fn check(&self) -> Result<(), String> {
+//!         if self.start < self.end {
+//!             Ok(())
+//!         } else {
+//!             Err("--start must be less than --end".to_string())
+//!         }
+//!     }
+//! }
+//!
+//! let err = Range::from_iter_safe(&["test", "--start", "5", "--end", "1"]).unwrap_err();
+//! assert_eq!(err.kind, ErrorKind::ValueValidation);
+//! ```
+//!
 //! ## Generics
 //!
 //! Generic structs and enums can be used. They require explicit trait bounds
@@ -1110,6 +1661,200 @@
 //!     custom_args: T,
 //! }
 //! ```
+//!
+//! ## Parsing a command line string
+//!
+//! [`StructOpt::from_line`] and [`StructOpt::from_line_safe`] parse a whole
+//! command line given as a single string, as typed in a console or a chat.
+//! The string is split with [`split_line`], which follows the quoting rules
+//! of a POSIX shell without doing any expansion, then parsed like
+//! [`StructOpt::from_iter`] and [`StructOpt::from_iter_safe`].
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(StructOpt, Debug, PartialEq)]
+//! enum Command {
+//!     Say { text: Vec<String> },
+//! }
+//!
+//! assert_eq!(
+//!     Command::from_line(r#"bot say "hello world" '$USER'"#),
+//!     Command::Say {
+//!         text: vec!["hello world".to_string(), "$USER".to_string()],
+//!     },
+//! );
+//! assert!(Command::from_line_safe("bot say 'unterminated").is_err());
+//! ```
+//!
+//! The [`repl`] module builds on it to read commands line after line, from
+//! the terminal or any [`BufRead`](std::io::BufRead), without exiting on
+//! errors or `--help`.
+//!
+//! ## Updating an existing value
+//!
+//! [`StructOpt::update_from_iter`] and [`StructOpt::update_from_iter_safe`]
+//! parse new arguments into an existing value, overwriting only the fields
+//! whose arguments are present and leaving the others untouched, which is
+//! handy to apply commands to the settings of a long-running session.
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(StructOpt, Debug, PartialEq)]
+//! struct Settings {
+//!     #[structopt(long, default_value = "4")]
+//!     jobs: u32,
+//!     #[structopt(long)]
+//!     verbose: bool,
+//! }
+//!
+//! let mut settings = Settings::from_iter(&["app", "--jobs", "8"]);
+//! settings.update_from_iter(&["app", "--verbose"]);
+//! assert_eq!(settings, Settings { jobs: 8, verbose: true });
+//! ```
+//!
+//! Flattened structs are updated the same way, as is the active subcommand
+//! when it's given again; a different subcommand replaces it.
+//!
+//! The required arguments and the required subcommand of the struct don't
+//! have to be given again, nor do those of its flattened structs. The
+//! arguments of a subcommand stay required, since it may replace the active
+//! one.
+//!
+//! ## Building from a map
+//!
+//! [`StructOpt::from_map`] builds a value from argument names and
+//! [`map::Value`]s, for example decoded from a JSON object received by a
+//! daemon. The map is turned into the equivalent command line, so it goes
+//! through exactly the same parsers, validators, default values and required
+//! checks as the command line. See [the `map` module](map) for details.
+//!
+//! ## Exit codes
+//!
+//! By default, [`StructOpt::from_args`] and [`StructOpt::from_iter`] exit with
+//! `1` on parse errors and `0` after displaying `--help` or `--version`. The
+//! top-level `exit_code = expr` attribute replaces this: `expr` must be callable
+//! as `fn(clap::ErrorKind) -> i32`, and its result is used as the exit code
+//! after the usual message is printed. [`sysexits`] maps errors to the codes
+//! of `sysexits.h`.
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(StructOpt)]
+//! #[structopt(exit_code = structopt::sysexits)]
+//! struct Opt {
+//!     #[structopt(short, long)]
+//!     count: u32,
+//! }
+//!
+//! #[derive(StructOpt)]
+//! #[structopt(exit_code = |kind| match kind {
+//!     structopt::clap::ErrorKind::ValueValidation => 3,
+//!     _ => 2,
+//! })]
+//! struct Other {
+//!     count: u32,
+//! }
+//! ```
+//!
+//! The `_safe` methods are not affected: they return the [`clap::Error`] as
+//! usual.
+//!
+//! ## Machine-readable errors
+//!
+//! When the `STRUCTOPT_ERROR_FORMAT` environment variable is `json`,
+//! [`StructOpt::from_args`] and [`StructOpt::from_iter`] print parse errors to
+//! stderr as a single line of JSON instead of the usual message, so that
+//! programs wrapping the CLI can show them in their own UI. The top-level
+//! `error_format = "json"` attribute makes it the default, which
+//! `STRUCTOPT_ERROR_FORMAT=text` reverts. `--help` and `--version` are
+//! printed as usual.
+//!
+//! ```
+//! # use structopt::StructOpt;
+//! #[derive(StructOpt)]
+//! #[structopt(error_format = "json")]
+//! struct Opt {
+//!     #[structopt(long)]
+//!     max_depth: u32,
+//! }
+//! ```
+//!
+//! With `--max-depth=ten`, this prints:
+//!
+//! ```text
+//! {"kind":"ValueValidation","message":"Invalid value for '--max-depth <max-depth>': invalid digit found in string","argument":"max-depth","value":"ten","suggestions":[]}
+//! ```
+//!
+//! `argument` is the name of the offending argument (after `rename_all`),
+//! `value` the rejected value, unknown argument or unknown subcommand, and
+//! `suggestions` lists the "did you mean" candidates. `argument` and `value`
+//! are `null` when the error doesn't name them. The same report can be built
+//! from the error returned by the `_safe` methods with [`ErrorReport::new`].
+//!
+//! ## `#[structopt::main]`
+//!
+//! The `#[structopt::main]` attribute turns `fn main(opt: Opt) -> Result<(), E>`
+//! into a `main` that parses `Opt` with [`StructOpt::from_args`] (exiting on
+//! parse errors) and calls the original function. If it returns an error,
+//! `error: {}` is printed to stderr using the [`Display`](std::fmt::Display)
+//! implementation of `E`, and the process exits with the code given by
+//! [`MainError::exit_code`]. The function may also return `()`.
+//!
+//! ```no_run
+//! # use structopt::{MainError, StructOpt};
+//! # use std::fmt;
+//! #[derive(StructOpt)]
+//! struct Opt {
+//!     #[structopt(short, long)]
+//!     count: u32,
+//! }
+//!
+//! #[derive(Debug)]
+//! struct TooMany;
+//!
+//! impl fmt::Display for TooMany {
+//!     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+//!         write!(f, "too many")
+//!     }
+//! }
+//!
+//! impl MainError for TooMany {
+//!     fn exit_code(&self) -> i32 {
+//!         2
+//!     }
+//! }
+//!
+//! #[structopt::main]
+//! fn main(opt: Opt) -> Result<(), TooMany> {
+//!     if opt.count > 10 {
+//!         return Err(TooMany);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`MainError`] is implemented for `String`, `&str`, [`std::io::Error`],
+//! `Box<dyn Error>` and a few others, with the default exit code `1`.
+//! Errors of foreign types, such as `anyhow::Error`, can be returned as a
+//! [`Failure`], which any [`Display`](std::fmt::Display) type converts into
+//! with `?`:
+//!
+//! ```no_run
+//! # use structopt::{Failure, StructOpt};
+//! # #[derive(StructOpt)]
+//! # struct Opt {
+//! #     path: std::path::PathBuf,
+//! # }
+//! #[structopt::main]
+//! fn main(opt: Opt) -> Result<(), Failure> {
+//!     let text = std::fs::read_to_string(&opt.path)?;
+//!     if text.is_empty() {
+//!         // EX_DATAERR
+//!         return Err(Failure::new(65, "empty input"));
+//!     }
+//!     Ok(())
+//! }
+//! ```
 
 // those mains are for a reason
 #![allow(clippy::needless_doctest_main)]
@@ -1128,6 +1873,314 @@ pub use paw_dep as paw;
 #[doc(hidden)]
 pub use lazy_static;
 
+#[cfg(feature = "dotenv")]
+pub mod dotenv;
+pub mod map;
+pub mod parsers;
+pub mod repl;
+mod secret;
+mod value_parser;
+
+#[doc(hidden)]
+#[path = "private.rs"]
+pub mod __private;
+
+pub use secret::Secret;
+pub use value_parser::{ContextParser, ParseContext, ValueParser};
+
+/// Turns `app` into the one parsing the arguments of an update, where its
+/// required arguments and subcommand can be left out. The arguments of its
+/// subcommands are unchanged, since a subcommand may replace the active one.
+fn update_app<'a, 'b>(mut app: clap::App<'a, 'b>) -> clap::App<'a, 'b> {
+    use clap::{AppSettings, ArgSettings};
+
+    app.p.required.clear();
+    for group in &mut app.p.groups {
+        group.required = false;
+    }
+    for flag in &mut app.p.flags {
+        flag.b.unset(ArgSettings::Required);
+    }
+    for opt in &mut app.p.opts {
+        opt.b.unset(ArgSettings::Required);
+    }
+    for (_, pos) in &mut app.p.positionals {
+        pos.b.unset(ArgSettings::Required);
+    }
+    app.unset_settings(&[
+        AppSettings::ArgRequiredElseHelp,
+        AppSettings::SubcommandRequired,
+        AppSettings::SubcommandRequiredElseHelp,
+    ])
+}
+
+/// Panics if two arguments of `app` or of one of its subcommands have the same
+/// short or long flag.
+fn debug_assert_app(app: &clap::App<'_, '_>) {
+    let switches = app
+        .p
+        .flags
+        .iter()
+        .map(|flag| (flag.b.name, &flag.s))
+        .chain(app.p.opts.iter().map(|opt| (opt.b.name, &opt.s)));
+    let mut seen: Vec<(String, &str)> = Vec::new();
+    for (name, switch) in switches {
+        let short = switch.short.map(|short| format!("-{}", short));
+        let long = switch.long.map(|long| format!("--{}", long));
+        for flag in short.into_iter().chain(long) {
+            if let Some((_, other)) = seen.iter().find(|(seen, _)| *seen == flag) {
+                panic!(
+                    "the arguments '{}' and '{}' of '{}' both use {}",
+                    other,
+                    name,
+                    app.get_name(),
+                    flag
+                );
+            }
+            seen.push((flag, name));
+        }
+    }
+
+    for subcommand in &app.p.subcommands {
+        debug_assert_app(subcommand);
+    }
+}
+
+/// How [`StructOpt::from_args`] and [`StructOpt::from_iter`] print parse errors.
+///
+/// See [the module documentation](index.html#machine-readable-errors).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// The usual human-readable message.
+    Text,
+    /// A single line of JSON, see [`ErrorReport`].
+    Json,
+}
+
+impl ErrorFormat {
+    /// Reads the `STRUCTOPT_ERROR_FORMAT` environment variable, which may be
+    /// `json` or `text`. Any other value is ignored.
+    pub fn from_env() -> Option<Self> {
+        match std::env::var_os("STRUCTOPT_ERROR_FORMAT")?.to_str()? {
+            "json" => Some(ErrorFormat::Json),
+            "text" => Some(ErrorFormat::Text),
+            _ => None,
+        }
+    }
+}
+
+/// A machine-readable description of a parse error.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorReport {
+    /// The kind of error.
+    pub kind: clap::ErrorKind,
+    /// The first line of the error message, without the `error: ` prefix.
+    pub message: String,
+    /// The name of the offending argument, as used by clap (the field name
+    /// after `rename_all`), when known.
+    pub argument: Option<String>,
+    /// The offending value, unknown argument or unknown subcommand, when known.
+    pub value: Option<String>,
+    /// The "did you mean" suggestions.
+    pub suggestions: Vec<String>,
+}
+
+impl ErrorReport {
+    /// Extracts the report from a clap error.
+    ///
+    /// The argument, value and suggestions come from [`clap::Error::info`],
+    /// which the parsing methods of [`StructOpt`] fill in. Errors returned by
+    /// [`clap::App`] directly lack the value rejected by a validator and the
+    /// suggestions.
+    pub fn new(err: &clap::Error) -> Self {
+        use clap::ErrorKind::*;
+
+        let message = strip_ansi(&err.message);
+        let info = err.info.as_deref().unwrap_or(&[]);
+        let (argument, value, suggestions) = match err.kind {
+            InvalidValue | TooManyValues | ValueValidation => (
+                info.first().cloned(),
+                info.get(1).cloned(),
+                info.get(2..).unwrap_or(&[]),
+            ),
+            EmptyValue
+            | TooFewValues
+            | WrongNumberOfValues
+            | UnexpectedMultipleUsage
+            | ArgumentConflict => (info.first().cloned(), None, &[][..]),
+            UnknownArgument | InvalidSubcommand | UnrecognizedSubcommand => {
+                (None, info.first().cloned(), info.get(1..).unwrap_or(&[]))
+            }
+            _ => (None, None, &[][..]),
+        };
+        let suggestions = suggestions.to_vec();
+
+        let first_line = message.lines().next().unwrap_or("");
+        ErrorReport {
+            kind: err.kind,
+            message: first_line
+                .strip_prefix("error: ")
+                .unwrap_or(first_line)
+                .to_owned(),
+            argument,
+            value,
+            suggestions,
+        }
+    }
+
+    /// Serializes the report as a JSON object, on a single line.
+    ///
+    /// `kind` is the name of the [`clap::ErrorKind`] variant, `argument` and
+    /// `value` are `null` when unknown.
+    pub fn to_json(&self) -> String {
+        fn string(s: &str) -> String {
+            let mut out = String::with_capacity(s.len() + 2);
+            out.push('"');
+            for c in s.chars() {
+                match c {
+                    '"' => out.push_str("\\\""),
+                    '\\' => out.push_str("\\\\"),
+                    '\n' => out.push_str("\\n"),
+                    '\r' => out.push_str("\\r"),
+                    '\t' => out.push_str("\\t"),
+                    c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => out.push(c),
+                }
+            }
+            out.push('"');
+            out
+        }
+
+        fn option(s: &Option<String>) -> String {
+            s.as_ref().map_or_else(|| "null".to_owned(), |s| string(s))
+        }
+
+        let suggestions: Vec<_> = self.suggestions.iter().map(|s| string(s)).collect();
+        format!(
+            "{{\"kind\":{},\"message\":{},\"argument\":{},\"value\":{},\"suggestions\":[{}]}}",
+            string(&format!("{:?}", self.kind)),
+            string(&self.message),
+            option(&self.argument),
+            option(&self.value),
+            suggestions.join(","),
+        )
+    }
+}
+
+/// Removes the color codes clap adds when printing to a terminal.
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // skip `ESC [ params final-byte`
+            for c in &mut chars {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Maps parse errors to the exit codes of BSD's `sysexits.h`, intended to be
+/// used as `#[structopt(exit_code = structopt::sysexits)]`.
+///
+/// `--help` and `--version` exit with `0` (`EX_OK`), I/O errors with `74`
+/// (`EX_IOERR`), formatting errors with `70` (`EX_SOFTWARE`) and every other
+/// error with `64` (`EX_USAGE`).
+pub fn sysexits(kind: clap::ErrorKind) -> i32 {
+    use clap::ErrorKind::*;
+
+    match kind {
+        HelpDisplayed | VersionDisplayed => 0,
+        Io => 74,
+        Format => 70,
+        _ => 64,
+    }
+}
+
+/// Splits a command line into arguments following the quoting rules of a
+/// POSIX shell, without any expansion.
+///
+/// Arguments are separated by unquoted blanks (spaces, tabs, carriage returns
+/// and newlines), so that lines ending with `\r\n` split as expected.
+/// Single quotes preserve everything up to the closing quote, double quotes
+/// preserve everything but `\` escapes of `"`, `\`, `$`, `` ` `` and newlines,
+/// and an unquoted `\` preserves the next character (a `\` followed by a
+/// newline is removed). `$`, `*`, `~` or `#` have no special meaning.
+///
+/// Unterminated quotes and a trailing `\` are reported as
+/// [`clap::ErrorKind::InvalidValue`] errors.
+///
+/// ```
+/// assert_eq!(
+///     structopt::split_line(r#"add "my file" it\'s '$HOME'"#).unwrap(),
+///     vec!["add", "my file", "it's", "$HOME"],
+/// );
+/// ```
+pub fn split_line(line: &str) -> Result<Vec<String>, clap::Error> {
+    let error = |msg: &str| {
+        clap::Error::with_description(
+            &format!("Can't split the command line: {}", msg),
+            clap::ErrorKind::InvalidValue,
+        )
+    };
+
+    let mut args = Vec::new();
+    // `None` between arguments, so that `''` gives an empty argument
+    let mut arg: Option<String> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\r' | '\n' => args.extend(arg.take()),
+            '\'' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => return Err(error("unterminated single quote")),
+                    }
+                }
+            }
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ '"') | Some(c @ '\\') | Some(c @ '$') | Some(c @ '`') => {
+                                arg.push(c)
+                            }
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => return Err(error("unterminated double quote")),
+                        },
+                        Some(c) => arg.push(c),
+                        None => return Err(error("unterminated double quote")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => arg.get_or_insert_with(String::new).push(c),
+                None => return Err(error("trailing backslash")),
+            },
+            c => arg.get_or_insert_with(String::new).push(c),
+        }
+    }
+    args.extend(arg);
+    Ok(args)
+}
+
 /// A struct that is converted from command line arguments.
 pub trait StructOpt {
     /// Returns [`clap::App`] corresponding to the struct.
@@ -1135,16 +2188,132 @@ pub trait StructOpt {
 
     /// Builds the struct from [`clap::ArgMatches`]. It's guaranteed to succeed
     /// if `matches` originates from an `App` generated by [`StructOpt::clap`] called on
-    /// the same type, otherwise it must panic.
+    /// the same type and [`StructOpt::check_matches`] accepts it, otherwise it must panic.
+    ///
+    /// [`StructOpt::try_from_clap`] returns the error instead.
     fn from_clap(matches: &clap::ArgMatches<'_>) -> Self;
 
+    /// Builds the struct from [`clap::ArgMatches`] like
+    /// [`StructOpt::from_clap`], once [`StructOpt::check_matches`] accepts
+    /// them, and checks it with [`StructOpt::validate`].
+    ///
+    /// Returns the first [`clap::Error`] of these checks.
+    fn try_from_clap(matches: &clap::ArgMatches<'_>) -> Result<Self, clap::Error>
+    where
+        Self: Sized,
+    {
+        Self::check_matches(matches)?;
+        let value = Self::from_clap(matches);
+        value.validate()?;
+        Ok(value)
+    }
+
+    /// Updates the struct from [`clap::ArgMatches`], overwriting only the fields
+    /// whose arguments were given, see [`StructOpt::update_from_iter`]. The
+    /// same guarantees as [`StructOpt::from_clap`] apply, so `matches` must be
+    /// accepted by [`StructOpt::check_matches`] first.
+    ///
+    /// The default implementation replaces the whole value.
+    fn update_from_clap(&mut self, matches: &clap::ArgMatches<'_>)
+    where
+        Self: Sized,
+    {
+        *self = Self::from_clap(matches);
+    }
+
+    /// Checks the value built by [`StructOpt::from_clap`] with the functions
+    /// given by `#[structopt(validate = ...)]`, including those of flattened
+    /// structs and subcommands, see
+    /// [Cross-field validation](index.html#cross-field-validation).
+    ///
+    /// All the other constructors of this trait call it, and fail with its
+    /// error. The default implementation accepts any value.
+    fn validate(&self) -> Result<(), clap::Error> {
+        Ok(())
+    }
+
+    /// Checks the values of `matches` that clap can't, which
+    /// [`StructOpt::from_clap`] can't report the errors of: runs the parsers
+    /// given with `parse(..., context)`, and reads the environment variables
+    /// of the flags of `env_prefix` and the files of `env_file`, including
+    /// those of flattened structs and subcommands.
+    ///
+    /// All the other constructors of this trait call it. The default
+    /// implementation accepts any matches.
+    fn check_matches(_matches: &clap::ArgMatches<'_>) -> Result<(), clap::Error>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+
+    /// Prints `err` and exits, with the exit code and format given by
+    /// `#[structopt(exit_code = ...)]` and `#[structopt(error_format = ...)]`.
+    ///
+    /// **This is NOT PUBLIC API**.
+    #[doc(hidden)]
+    fn exit_on_error(err: &clap::Error) -> !
+    where
+        Self: Sized,
+    {
+        __private::exit_with_error(err, ErrorFormat::Text, None)
+    }
+
+    /// The arguments to parse instead of `args`, which may be changed by
+    /// `#[structopt(multicall)]`.
+    ///
+    /// **This is NOT PUBLIC API**.
+    #[doc(hidden)]
+    fn prepare_args(args: Vec<OsString>) -> Vec<OsString>
+    where
+        Self: Sized,
+    {
+        args
+    }
+
+    /// The `.env` file given by `#[structopt(dotenv = ...)]`.
+    ///
+    /// **This is NOT PUBLIC API**.
+    #[doc(hidden)]
+    fn dotenv_path() -> Option<&'static str>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// Panics if two arguments of a command have the same short or long flag,
+    /// in the whole tree of commands, including the `flatten`ed structs and
+    /// the subcommands.
+    ///
+    /// The derive rejects the duplicates among the fields of a struct at
+    /// compile time, but the ones coming from a flattened struct are only
+    /// detected when the [`clap::App`] is built, and only in debug builds.
+    /// Calling it from a test catches them before the users do:
+    ///
+    /// ```
+    /// # use structopt::StructOpt;
+    /// # #[derive(StructOpt)] struct Opt {}
+    /// // in `#[test] fn verify_cli()`
+    /// Opt::debug_assert();
+    /// ```
+    fn debug_assert()
+    where
+        Self: Sized,
+    {
+        debug_assert_app(&Self::clap());
+    }
+
     /// Builds the struct from the command line arguments ([`std::env::args_os`]).
     /// Calls [`clap::Error::exit`] on failure, printing the error message and aborting the program.
+    ///
+    /// With the `dotenv` feature, environment variables are also read from a
+    /// `.env` file, see [Loading `.env` files](index.html#loading-env-files).
     fn from_args() -> Self
     where
         Self: Sized,
     {
-        Self::from_clap(&Self::clap().get_matches())
+        Self::from_args_safe().unwrap_or_else(|err| Self::exit_on_error(&err))
     }
 
     /// Builds the struct from the command line arguments ([`std::env::args_os`]).
@@ -1154,14 +2323,17 @@ pub trait StructOpt {
     where
         Self: Sized,
     {
-        Self::clap()
-            .get_matches_safe()
-            .map(|matches| Self::from_clap(&matches))
+        let app = __private::args_app(Self::clap(), Self::dotenv_path())?;
+        let args = Self::prepare_args(std::env::args_os().collect());
+        Self::try_from_clap(&__private::get_matches_from_safe(app, args)?)
     }
 
     /// Gets the struct from any iterator such as a `Vec` of your making.
     /// Print the error message and quit the program in case of failure.
     ///
+    /// The error is printed as JSON when the `STRUCTOPT_ERROR_FORMAT`
+    /// environment variable is `json`, see [`ErrorFormat`].
+    ///
     /// **NOTE**: The first argument will be parsed as the binary name unless
     /// [`clap::AppSettings::NoBinaryName`] has been used.
     fn from_iter<I>(iter: I) -> Self
@@ -1170,7 +2342,7 @@ pub trait StructOpt {
         I: IntoIterator,
         I::Item: Into<OsString> + Clone,
     {
-        Self::from_clap(&Self::clap().get_matches_from(iter))
+        Self::from_iter_safe(iter).unwrap_or_else(|err| Self::exit_on_error(&err))
     }
 
     /// Gets the struct from any iterator such as a `Vec` of your making.
@@ -1187,7 +2359,215 @@ pub trait StructOpt {
         I: IntoIterator,
         I::Item: Into<OsString> + Clone,
     {
-        Ok(Self::from_clap(&Self::clap().get_matches_from_safe(iter)?))
+        let args = Self::prepare_args(iter.into_iter().map(Into::into).collect());
+        Self::try_from_clap(&__private::get_matches_from_safe(Self::clap(), args)?)
+    }
+
+    /// Updates the struct from any iterator such as a `Vec` of your making,
+    /// overwriting only the fields whose arguments are present. Print the
+    /// error message and quit the program in case of failure.
+    ///
+    /// Values coming from `default_value` or `env` don't count as present,
+    /// and the required arguments and subcommand of the struct can be left
+    /// out. Flattened structs are updated recursively. If a subcommand is given
+    /// and it's the active one, its fields are updated the same way,
+    /// otherwise it replaces the active one; if none is given, only the
+    /// [default subcommand](index.html#default-subcommand) is updated, when
+    /// active.
+    ///
+    /// **NOTE**: The first argument will be parsed as the binary name unless
+    /// [`clap::AppSettings::NoBinaryName`] has been used.
+    fn update_from_iter<I>(&mut self, iter: I)
+    where
+        Self: Sized,
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        if let Err(err) = self.update_from_iter_safe(iter) {
+            Self::exit_on_error(&err)
+        }
+    }
+
+    /// Updates the struct from any iterator such as a `Vec` of your making,
+    /// overwriting only the fields whose arguments are present, see
+    /// [`update_from_iter()`][StructOpt::update_from_iter].
+    ///
+    /// Returns a [`clap::Error`] in case of failure, leaving the struct
+    /// untouched, unless the error comes from
+    /// [`validate()`][StructOpt::validate], which checks the updated struct.
+    fn update_from_iter_safe<I>(&mut self, iter: I) -> Result<(), clap::Error>
+    where
+        Self: Sized,
+        I: IntoIterator,
+        I::Item: Into<OsString> + Clone,
+    {
+        let args = Self::prepare_args(iter.into_iter().map(Into::into).collect());
+        let matches = __private::get_matches_from_safe(update_app(Self::clap()), args)?;
+        Self::check_matches(&matches)?;
+        self.update_from_clap(&matches);
+        self.validate()
+    }
+
+    /// Builds the struct from the environment variables of its arguments only,
+    /// ignoring the command line, see
+    /// [`env_prefix`](index.html#configuring-from-the-environment-only). Print
+    /// the error message and quit the program in case of failure.
+    fn from_env() -> Self
+    where
+        Self: Sized,
+    {
+        match Self::from_env_safe() {
+            Ok(value) => value,
+            Err(err) => Self::exit_on_error(&err),
+        }
+    }
+
+    /// Builds the struct from the environment variables of its arguments only,
+    /// ignoring the command line.
+    ///
+    /// Returns a [`clap::Error`] in case of failure, like
+    /// [`from_iter_safe()`][StructOpt::from_iter_safe].
+    fn from_env_safe() -> Result<Self, clap::Error>
+    where
+        Self: Sized,
+    {
+        let app = Self::clap().setting(clap::AppSettings::NoBinaryName);
+        let matches = __private::get_matches_from_safe(app, std::iter::empty::<OsString>())?;
+        Self::try_from_clap(&matches)
+    }
+
+    /// Builds the struct from a map of argument names to [`map::Value`]s
+    /// instead of the command line, see [the `map` module](map).
+    ///
+    /// The map goes through the same parsers, validators, defaults and checks
+    /// as the command line, and errors are returned as [`clap::Error`], as with
+    /// [`from_iter_safe()`][StructOpt::from_iter_safe].
+    fn from_map<I, K, V>(map: I) -> Result<Self, clap::Error>
+    where
+        Self: Sized,
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<map::Value>,
+    {
+        let app = Self::clap().setting(clap::AppSettings::NoBinaryName);
+        let args = map::map_args(&app, map)?;
+        Self::try_from_clap(&__private::get_matches_from_safe(app, args)?)
+    }
+
+    /// Gets the struct from a single command line, split into arguments with
+    /// [`split_line`]. Print the error message and quit the program in case
+    /// of failure.
+    ///
+    /// **NOTE**: As with [`StructOpt::from_iter`], the first argument will be
+    /// parsed as the binary name unless [`clap::AppSettings::NoBinaryName`]
+    /// has been used.
+    fn from_line(line: &str) -> Self
+    where
+        Self: Sized,
+    {
+        match split_line(line) {
+            Ok(args) => Self::from_iter(args),
+            Err(err) => Self::exit_on_error(&err),
+        }
+    }
+
+    /// Gets the struct from a single command line, split into arguments with
+    /// [`split_line`].
+    ///
+    /// Returns a [`clap::Error`] in case of failure, like
+    /// [`from_iter_safe()`][StructOpt::from_iter_safe].
+    fn from_line_safe(line: &str) -> Result<Self, clap::Error>
+    where
+        Self: Sized,
+    {
+        Self::from_iter_safe(split_line(line)?)
+    }
+}
+
+/// An error returned from a [`#[structopt::main]`](index.html#structoptmain) function,
+/// setting the exit code of the process.
+pub trait MainError {
+    /// The exit code of the process, defaults to `1`.
+    fn exit_code(&self) -> i32 {
+        1
+    }
+}
+
+impl MainError for String {}
+impl MainError for &str {}
+impl MainError for std::io::Error {}
+impl MainError for std::fmt::Error {}
+impl MainError for Box<dyn std::error::Error> {}
+impl MainError for Box<dyn std::error::Error + Send + Sync> {}
+
+impl MainError for clap::Error {
+    fn exit_code(&self) -> i32 {
+        if self.use_stderr() {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+/// An error message with an exit code, returned from a
+/// [`#[structopt::main]`](index.html#structoptmain) function.
+///
+/// Any [`Display`](std::fmt::Display) type converts into a `Failure` with the
+/// exit code `1`, so `?` works on errors of foreign types that can't implement
+/// [`MainError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    code: i32,
+    message: String,
+}
+
+impl Failure {
+    /// Creates a failure exiting with `code` and printing `message`.
+    pub fn new(code: i32, message: impl std::fmt::Display) -> Self {
+        Failure {
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    /// The exit code of the process.
+    pub fn code(&self) -> i32 {
+        self.code
+    }
+
+    /// The message printed to stderr.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+// `Failure` doesn't implement `Display` so that this doesn't overlap with
+// `impl<T> From<T> for T`.
+impl<E: std::fmt::Display> From<E> for Failure {
+    fn from(err: E) -> Self {
+        Failure::new(1, err)
+    }
+}
+
+/// A command that can be executed with a context of type `C`.
+///
+/// It is implemented by `#[derive(StructOpt)]` on enums marked with
+/// `#[structopt(run(...))]`, see [running subcommands](index.html#running-subcommands),
+/// and may be implemented by hand for the types wrapped by their variants.
+pub trait StructOptRun<C: ?Sized = ()> {
+    /// The value returned by [`StructOptRun::run`].
+    type Output;
+
+    /// Executes the command.
+    fn run(self, ctx: &C) -> Self::Output;
+}
+
+impl<C: ?Sized, T: StructOptRun<C>> StructOptRun<C> for Box<T> {
+    type Output = T::Output;
+
+    fn run(self, ctx: &C) -> Self::Output {
+        (*self).run(ctx)
     }
 }
 
@@ -1208,6 +2588,41 @@ pub trait StructOptInternal: StructOpt {
     {
         None
     }
+
+    fn has_subcommand(_name: &str) -> bool {
+        false
+    }
+
+    fn has_default_subcommand() -> bool {
+        false
+    }
+
+    fn from_default_subcommand(_matches: &clap::ArgMatches<'_>) -> Option<Self>
+    where
+        Self: std::marker::Sized,
+    {
+        None
+    }
+
+    fn update_from_subcommand<'a, 'b>(
+        &mut self,
+        sub: (&'b str, Option<&'b clap::ArgMatches<'a>>),
+    ) -> bool
+    where
+        Self: std::marker::Sized,
+    {
+        match Self::from_subcommand(sub) {
+            Some(new) => {
+                *self = new;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn update_from_default_subcommand(&mut self, _matches: &clap::ArgMatches<'_>) -> bool {
+        false
+    }
 }
 
 impl<T: StructOpt> StructOpt for Box<T> {
@@ -1218,6 +2633,30 @@ impl<T: StructOpt> StructOpt for Box<T> {
     fn from_clap(matches: &clap::ArgMatches<'_>) -> Self {
         Box::new(<T as StructOpt>::from_clap(matches))
     }
+
+    fn update_from_clap(&mut self, matches: &clap::ArgMatches<'_>) {
+        <T as StructOpt>::update_from_clap(self, matches)
+    }
+
+    fn validate(&self) -> Result<(), clap::Error> {
+        <T as StructOpt>::validate(self)
+    }
+
+    fn check_matches(matches: &clap::ArgMatches<'_>) -> Result<(), clap::Error> {
+        <T as StructOpt>::check_matches(matches)
+    }
+
+    fn exit_on_error(err: &clap::Error) -> ! {
+        <T as StructOpt>::exit_on_error(err)
+    }
+
+    fn prepare_args(args: Vec<OsString>) -> Vec<OsString> {
+        <T as StructOpt>::prepare_args(args)
+    }
+
+    fn dotenv_path() -> Option<&'static str> {
+        <T as StructOpt>::dotenv_path()
+    }
 }
 
 impl<T: StructOptInternal> StructOptInternal for Box<T> {
@@ -1235,4 +2674,32 @@ impl<T: StructOptInternal> StructOptInternal for Box<T> {
     fn augment_clap<'a, 'b>(app: clap::App<'a, 'b>) -> clap::App<'a, 'b> {
         <T as StructOptInternal>::augment_clap(app)
     }
+
+    #[doc(hidden)]
+    fn has_subcommand(name: &str) -> bool {
+        <T as StructOptInternal>::has_subcommand(name)
+    }
+
+    #[doc(hidden)]
+    fn has_default_subcommand() -> bool {
+        <T as StructOptInternal>::has_default_subcommand()
+    }
+
+    #[doc(hidden)]
+    fn from_default_subcommand(matches: &clap::ArgMatches<'_>) -> Option<Self> {
+        <T as StructOptInternal>::from_default_subcommand(matches).map(Box::new)
+    }
+
+    #[doc(hidden)]
+    fn update_from_subcommand<'a, 'b>(
+        &mut self,
+        sub: (&'b str, Option<&'b clap::ArgMatches<'a>>),
+    ) -> bool {
+        <T as StructOptInternal>::update_from_subcommand(self, sub)
+    }
+
+    #[doc(hidden)]
+    fn update_from_default_subcommand(&mut self, matches: &clap::ArgMatches<'_>) -> bool {
+        <T as StructOptInternal>::update_from_default_subcommand(self, matches)
+    }
 }
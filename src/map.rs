@@ -0,0 +1,262 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Values for [`StructOpt::from_map`](crate::StructOpt::from_map).
+//!
+//! Each key of the map is the name of an argument, as used by clap (the field
+//! name after `rename_all`), or of a subcommand. The map is turned into the
+//! equivalent command line, which is then parsed as usual, so parsers,
+//! validators, defaults and required arguments behave exactly as with
+//! [`StructOpt::from_iter_safe`](crate::StructOpt::from_iter_safe).
+//!
+//! - A flag is given if its value is `true`, or as many times as an integer
+//!   value says (for `parse(from_occurrences)`).
+//! - An option or a positional argument is given once per element of a
+//!   [`Value::List`], or once with any other scalar value, converted to a
+//!   string.
+//! - A subcommand is given with a [`Value::Map`] of its own arguments. The
+//!   positional arguments given along with a subcommand can't start with `-`
+//!   or be the name of a subcommand, as the command line couldn't tell them
+//!   apart from flags and subcommands.
+//!
+//! ```
+//! use structopt::{map::Value, StructOpt};
+//!
+//! #[derive(StructOpt, Debug, PartialEq)]
+//! struct Opt {
+//!     #[structopt(short, long)]
+//!     verbose: bool,
+//!     #[structopt(long, default_value = "1")]
+//!     jobs: u32,
+//!     files: Vec<String>,
+//! }
+//!
+//! let opt = Opt::from_map(vec![
+//!     ("verbose", Value::from(true)),
+//!     ("files", Value::from(vec!["a", "-b"])),
+//! ])
+//! .unwrap();
+//! assert_eq!(
+//!     opt,
+//!     Opt {
+//!         verbose: true,
+//!         jobs: 1,
+//!         files: vec!["a".to_string(), "-b".to_string()],
+//!     }
+//! );
+//!
+//! assert!(Opt::from_map(vec![("jobs", Value::from("many"))]).is_err());
+//! ```
+
+use std::ffi::OsString;
+
+use crate::clap::{App, ArgSettings, Error, ErrorKind};
+
+/// A value of [`StructOpt::from_map`](crate::StructOpt::from_map).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A boolean, giving a flag or `true`/`false`.
+    Bool(bool),
+    /// An integer, giving a flag this many times or a number.
+    Int(i64),
+    /// A floating point number.
+    Float(f64),
+    /// A string.
+    Str(String),
+    /// Several values of an argument.
+    List(Vec<Value>),
+    /// The arguments of a subcommand.
+    Map(Vec<(String, Value)>),
+}
+
+macro_rules! impl_from {
+    ($($ty:ty => $variant:ident),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(v: $ty) -> Self {
+                    Value::$variant(v.into())
+                }
+            }
+        )*
+    };
+}
+
+impl_from! {
+    bool => Bool,
+    i8 => Int, i16 => Int, i32 => Int, i64 => Int,
+    u8 => Int, u16 => Int, u32 => Int,
+    f32 => Float, f64 => Float,
+    &str => Str, String => Str
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self {
+        Value::List(v.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Value {
+    fn to_arg(&self, name: &str) -> Result<String, Error> {
+        match self {
+            Value::Bool(b) => Ok(b.to_string()),
+            Value::Int(i) => Ok(i.to_string()),
+            Value::Float(f) => Ok(f.to_string()),
+            Value::Str(s) => Ok(s.clone()),
+            Value::List(_) | Value::Map(_) => Err(invalid_value(name, "a string or a number")),
+        }
+    }
+
+    fn to_args(&self, name: &str, multiple: bool) -> Result<Vec<String>, Error> {
+        match self {
+            Value::List(values) if multiple => values.iter().map(|v| v.to_arg(name)).collect(),
+            Value::List(_) => Err(invalid_value(name, "a single value")),
+            value => Ok(vec![value.to_arg(name)?]),
+        }
+    }
+}
+
+/// Whether the positional `value` would be taken as a flag or a subcommand of
+/// `app` without a `--` before it.
+fn is_ambiguous(app: &App<'_, '_>, value: &str) -> bool {
+    value.starts_with('-')
+        || value == "help"
+        || app.p.subcommands.iter().any(|sub| {
+            sub.get_name() == value
+                || sub
+                    .p
+                    .meta
+                    .aliases
+                    .iter()
+                    .flatten()
+                    .any(|&(alias, _)| alias == value)
+        })
+}
+
+fn invalid_value(name: &str, expected: &str) -> Error {
+    Error::with_description(
+        &format!("Invalid value for '{}': expected {}", name, expected),
+        ErrorKind::InvalidValue,
+    )
+}
+
+/// Turns `map` into the command line giving the same arguments to `app`,
+/// without the binary name.
+///
+/// **This is NOT PUBLIC API**.
+#[doc(hidden)]
+pub fn map_args<I, K, V>(app: &App<'_, '_>, map: I) -> Result<Vec<OsString>, Error>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<Value>,
+{
+    let map = map.into_iter().map(|(k, v)| (k.into(), v.into())).collect();
+    let mut args = Vec::new();
+    push_args(app, map, &mut args)?;
+    Ok(args.into_iter().map(OsString::from).collect())
+}
+
+// clap 2 has no public way to list the arguments of an `App`, so we look at
+// the fields of its parser, which are public but hidden.
+fn push_args(
+    app: &App<'_, '_>,
+    map: Vec<(String, Value)>,
+    args: &mut Vec<String>,
+) -> Result<(), Error> {
+    let mut positionals = Vec::new();
+    let mut subcommand = None;
+
+    for (name, value) in map {
+        if let Some(flag) = app.p.flags.iter().find(|f| f.b.name == name) {
+            let occurrences = match value {
+                Value::Bool(b) => b as i64,
+                Value::Int(n) if n >= 0 => n,
+                _ => return Err(invalid_value(&name, "a boolean or a number of occurrences")),
+            };
+            let arg = match (flag.s.long, flag.s.short) {
+                (Some(long), _) => format!("--{}", long),
+                (None, Some(short)) => format!("-{}", short),
+                (None, None) => unreachable!("flags have a long or a short"),
+            };
+            args.extend((0..occurrences).map(|_| arg.clone()));
+        } else if let Some(opt) = app.p.opts.iter().find(|o| o.b.name == name) {
+            for value in value.to_args(&name, opt.b.is_set(ArgSettings::Multiple))? {
+                // `=` keeps values starting with `-` from being taken as flags
+                args.push(match (opt.s.long, opt.s.short) {
+                    (Some(long), _) => format!("--{}={}", long, value),
+                    (None, Some(short)) => format!("-{}={}", short, value),
+                    (None, None) => unreachable!("options have a long or a short"),
+                });
+            }
+        } else if let Some(pos) = app.p.positionals.values().find(|p| p.b.name == name) {
+            let values = value.to_args(&name, pos.b.is_set(ArgSettings::Multiple))?;
+            positionals.push((pos.index, pos.b.name, values));
+        } else if let Some(sub) = app.p.subcommands.iter().find(|s| s.get_name() == name) {
+            if subcommand.is_some() {
+                return Err(Error::with_description(
+                    &format!("The subcommand '{}' cannot be used with another one", name),
+                    ErrorKind::ArgumentConflict,
+                ));
+            }
+            match value {
+                Value::Map(map) => subcommand = Some((sub, map)),
+                _ => return Err(invalid_value(&name, "a map of arguments")),
+            }
+        } else {
+            return Err(Error::with_description(
+                &format!("Found argument '{}' which wasn't expected", name),
+                ErrorKind::UnknownArgument,
+            ));
+        }
+    }
+
+    positionals.sort_by_key(|(index, _, _)| *index);
+    for (expected, (index, name, _)) in (1..).zip(&positionals) {
+        if *index != expected {
+            let missing = app.p.positionals.values().find(|p| p.index == expected);
+            return Err(Error::with_description(
+                &format!(
+                    "The argument '{}' can't be given without '{}'",
+                    name,
+                    missing.map_or("", |p| p.b.name)
+                ),
+                ErrorKind::MissingRequiredArgument,
+            ));
+        }
+    }
+
+    match subcommand {
+        Some((sub, map)) => {
+            // a `--` would turn the subcommand into a positional argument, so
+            // the values must not look like a flag or a subcommand instead
+            for (_, name, values) in &positionals {
+                if let Some(value) = values.iter().find(|v| is_ambiguous(app, v)) {
+                    let mut err = Error::with_description(
+                        &format!(
+                            "Invalid value for '{}': '{}' can't be given along with a subcommand",
+                            name, value
+                        ),
+                        ErrorKind::InvalidValue,
+                    );
+                    err.info = Some(vec![name.to_string(), value.clone()]);
+                    return Err(err);
+                }
+            }
+            args.extend(positionals.into_iter().flat_map(|(_, _, values)| values));
+            args.push(sub.get_name().to_string());
+            push_args(sub, map, args)
+        }
+        None => {
+            if !positionals.is_empty() {
+                args.push("--".to_string());
+                args.extend(positionals.into_iter().flat_map(|(_, _, values)| values));
+            }
+            Ok(())
+        }
+    }
+}
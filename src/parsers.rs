@@ -0,0 +1,389 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Parsers for the values often found in command lines, usable with
+//! `parse(try_from_str = ...)`.
+//!
+//! - [`duration`] parses human durations like `1h30m` or `250ms`. It's the
+//!   default parser of the fields of type [`Duration`].
+//! - [`byte_size`] parses sizes like `10MiB` or `1.5G`, also available as the
+//!   [`ByteSize`] type.
+//! - [`int`] parses integers with a `0x`, `0o` or `0b` prefix and `_`
+//!   separators.
+//! - [`ranges`] parses lists of integers and ranges like `1-5,9`, also
+//!   available as the [`Ranges`] type.
+//!
+//! ```
+//! use std::time::Duration;
+//! use structopt::parsers::{self, ByteSize, Ranges};
+//! use structopt::StructOpt;
+//!
+//! #[derive(StructOpt, Debug)]
+//! struct Opt {
+//!     #[structopt(long)]
+//!     timeout: Duration,
+//!     #[structopt(long)]
+//!     max_size: ByteSize,
+//!     #[structopt(long, parse(try_from_str = parsers::int))]
+//!     mode: u32,
+//!     #[structopt(long)]
+//!     cpus: Ranges<u16>,
+//! }
+//!
+//! let opt = Opt::from_iter(&[
+//!     "test", "--timeout", "1m30s", "--max-size", "10MiB", "--mode", "0o755", "--cpus", "0-3,8",
+//! ]);
+//! assert_eq!(opt.timeout, Duration::from_secs(90));
+//! assert_eq!(opt.max_size, ByteSize(10 * 1024 * 1024));
+//! assert_eq!(opt.mode, 0o755);
+//! assert_eq!(opt.cpus.iter().collect::<Vec<_>>(), [0, 1, 2, 3, 8]);
+//! ```
+
+use std::fmt;
+use std::num::ParseIntError;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The error of the parsers of this module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    message: String,
+}
+
+impl ParseError {
+    fn new(message: String) -> Self {
+        ParseError { message }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a duration made of numbers followed by their unit, like `1h30m`,
+/// `250ms` or `1.5 days`.
+///
+/// The units are `ns`, `us` (or `µs`), `ms`, `s` (or `sec`), `m` (or
+/// `min`), `h` (or `hour`) and `d` (or `day`), in the singular or plural.
+///
+/// ```
+/// use std::time::Duration;
+/// use structopt::parsers::duration;
+///
+/// assert_eq!(duration("1h30m"), Ok(Duration::from_secs(5400)));
+/// assert_eq!(duration("250ms"), Ok(Duration::from_millis(250)));
+/// assert_eq!(duration("1.5s"), Ok(Duration::from_millis(1500)));
+/// assert!(duration("10").is_err());
+/// ```
+pub fn duration(src: &str) -> Result<Duration, ParseError> {
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+    let mut rest = src.trim();
+    if rest.is_empty() {
+        return Err(ParseError::new("the duration is empty".into()));
+    }
+
+    let mut nanos: u128 = 0;
+    while !rest.is_empty() {
+        let (number, after) = split_number(rest, src)?;
+        let after = after.trim_start();
+        let unit_len = after
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(after.len());
+        let (unit, after) = after.split_at(unit_len);
+
+        let unit_nanos = match unit {
+            "ns" | "nsec" | "nsecs" => 1,
+            "us" | "µs" | "usec" | "usecs" => 1_000,
+            "ms" | "msec" | "msecs" => 1_000_000,
+            "s" | "sec" | "secs" | "second" | "seconds" => NANOS_PER_SEC,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60 * NANOS_PER_SEC,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600 * NANOS_PER_SEC,
+            "d" | "day" | "days" => 86400 * NANOS_PER_SEC,
+            "" => {
+                return Err(ParseError::new(format!(
+                    "missing a unit after `{}` in `{}`, like `{}s`",
+                    number, src, number
+                )))
+            }
+            _ => {
+                return Err(ParseError::new(format!(
+                    "unknown unit `{}` in `{}`, expected one of ns, us, ms, s, m, h, d",
+                    unit, src
+                )))
+            }
+        };
+
+        nanos = scale(number, unit_nanos)
+            .and_then(|value| nanos.checked_add(value))
+            .ok_or_else(|| ParseError::new(format!("the duration `{}` is too long", src)))?;
+        rest = after.trim_start();
+    }
+
+    let secs = nanos / NANOS_PER_SEC;
+    if secs > u128::from(u64::MAX) {
+        return Err(ParseError::new(format!(
+            "the duration `{}` is too long",
+            src
+        )));
+    }
+    Ok(Duration::new(secs as u64, (nanos % NANOS_PER_SEC) as u32))
+}
+
+/// Parses a number of bytes, like `512`, `4k`, `10MiB` or `1.5G`, rounded
+/// down to a whole number of bytes.
+///
+/// The units are case-insensitive: `B`, then `K`, `M`, `G`, `T`, `P` and
+/// `E` for the powers of 1000 and `Ki`, `Mi`, `Gi`, `Ti`, `Pi` and `Ei` for
+/// the powers of 1024, optionally followed by `B`.
+///
+/// ```
+/// use structopt::parsers::byte_size;
+///
+/// assert_eq!(byte_size("10MiB"), Ok(10 * 1024 * 1024));
+/// assert_eq!(byte_size("1.5G"), Ok(1_500_000_000));
+/// assert_eq!(byte_size("512"), Ok(512));
+/// ```
+pub fn byte_size(src: &str) -> Result<u64, ParseError> {
+    let (number, unit) = split_number(src.trim(), src)?;
+
+    let multiplier: u128 = match &*unit.trim_start().to_ascii_lowercase() {
+        "" | "b" => 1,
+        "k" | "kb" => 1000,
+        "ki" | "kib" => 1 << 10,
+        "m" | "mb" => 1000_u128.pow(2),
+        "mi" | "mib" => 1 << 20,
+        "g" | "gb" => 1000_u128.pow(3),
+        "gi" | "gib" => 1 << 30,
+        "t" | "tb" => 1000_u128.pow(4),
+        "ti" | "tib" => 1 << 40,
+        "p" | "pb" => 1000_u128.pow(5),
+        "pi" | "pib" => 1 << 50,
+        "e" | "eb" => 1000_u128.pow(6),
+        "ei" | "eib" => 1 << 60,
+        _ => {
+            return Err(ParseError::new(format!(
+                "unknown unit `{}` in `{}`, expected one of B, K, KiB, M, MiB, G, GiB, T, TiB, \
+                 P, PiB, E, EiB",
+                unit.trim_start(),
+                src
+            )))
+        }
+    };
+
+    scale(number, multiplier)
+        .filter(|&bytes| bytes <= u128::from(u64::MAX))
+        .map(|bytes| bytes as u64)
+        .ok_or_else(|| ParseError::new(format!("the size `{}` is too large", src)))
+}
+
+/// A number of bytes, parsed by [`byte_size`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+impl FromStr for ByteSize {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        byte_size(s).map(ByteSize)
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}B", self.0)
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> u64 {
+        size.0
+    }
+}
+
+/// The primitive integer types, parsed by [`int`] and [`ranges`].
+pub trait Integer: Copy + PartialOrd + private::Sealed {
+    /// [`u32::from_str_radix`] for this type.
+    fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError>;
+
+    /// The next integer, if any.
+    fn successor(self) -> Option<Self>;
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_integer {
+    ($($ty:ty),*) => {$(
+        impl private::Sealed for $ty {}
+
+        impl Integer for $ty {
+            fn from_str_radix(src: &str, radix: u32) -> Result<Self, ParseIntError> {
+                <$ty>::from_str_radix(src, radix)
+            }
+
+            fn successor(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+        }
+    )*};
+}
+
+impl_integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Parses an integer, in hexadecimal with a `0x` prefix, in octal with `0o`
+/// or in binary with `0b`, and with `_` separating the digits if wanted.
+///
+/// ```
+/// use structopt::parsers::int;
+///
+/// assert_eq!(int::<u32>("0xff"), Ok(255));
+/// assert_eq!(int::<u32>("0b1010"), Ok(10));
+/// assert_eq!(int::<i64>("-1_000_000"), Ok(-1_000_000));
+/// ```
+pub fn int<T: Integer>(src: &str) -> Result<T, ParseError> {
+    let s = src.trim();
+    let (sign, s) = match s.chars().next() {
+        Some(c @ '-') | Some(c @ '+') => (Some(c), &s[1..]),
+        _ => (None, s),
+    };
+    let (radix, digits) = match s.get(..2) {
+        Some("0x") | Some("0X") => (16, &s[2..]),
+        Some("0o") | Some("0O") => (8, &s[2..]),
+        Some("0b") | Some("0B") => (2, &s[2..]),
+        _ => (10, s),
+    };
+    // `from_str_radix` would accept a second sign
+    if digits.starts_with(&['-', '+'][..]) {
+        return Err(ParseError::new(format!(
+            "invalid number `{}`: the sign must come first",
+            src
+        )));
+    }
+
+    let number: String = sign
+        .into_iter()
+        .chain(digits.chars().filter(|&c| c != '_'))
+        .collect();
+    T::from_str_radix(&number, radix)
+        .map_err(|e| ParseError::new(format!("invalid number `{}`: {}", src, e)))
+}
+
+/// A list of integers and of inclusive ranges of integers, parsed by
+/// [`ranges`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ranges<T>(Vec<RangeInclusive<T>>);
+
+impl<T: Integer> Ranges<T> {
+    /// The ranges in the order they were given, a single integer being a
+    /// range of one integer.
+    pub fn ranges(&self) -> &[RangeInclusive<T>] {
+        &self.0
+    }
+
+    /// Returns whether `value` is in one of the ranges.
+    pub fn contains(&self, value: &T) -> bool {
+        self.0.iter().any(|range| range.contains(value))
+    }
+
+    /// Iterates over the integers of the ranges, in the order they were given.
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        self.0.iter().flat_map(|range| {
+            let end = *range.end();
+            std::iter::successors(Some(*range.start()), |value| value.successor())
+                .take_while(move |value| *value <= end)
+        })
+    }
+}
+
+impl<T: Integer> FromStr for Ranges<T> {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        ranges(s)
+    }
+}
+
+/// Parses a comma-separated list of integers and of inclusive ranges of
+/// integers, like `1-5,9`. The integers are parsed by [`int`].
+///
+/// ```
+/// use structopt::parsers::ranges;
+///
+/// let cpus = ranges::<u16>("1-3,8").unwrap();
+/// assert_eq!(cpus.ranges(), [1..=3, 8..=8]);
+/// assert!(cpus.contains(&2));
+/// assert_eq!(cpus.iter().collect::<Vec<_>>(), [1, 2, 3, 8]);
+/// ```
+pub fn ranges<T: Integer>(src: &str) -> Result<Ranges<T>, ParseError> {
+    src.split(',')
+        .map(|item| {
+            let item = item.trim();
+            if item.is_empty() {
+                return Err(ParseError::new(format!("empty item in `{}`", src)));
+            }
+            // the `-` of a negative start isn't a separator
+            let separator = item.char_indices().skip(1).find(|&(_, c)| c == '-');
+            let range = match separator {
+                Some((index, _)) => int(&item[..index])?..=int(&item[index + 1..])?,
+                None => int(item)?..=int(item)?,
+            };
+            if range.start() > range.end() {
+                return Err(ParseError::new(format!(
+                    "`{}` is not a range, its start is after its end",
+                    item
+                )));
+            }
+            Ok(range)
+        })
+        .collect::<Result<_, _>>()
+        .map(Ranges)
+}
+
+/// Splits `s`, a part of `src`, after the decimal number it starts with.
+fn split_number<'a>(s: &'a str, src: &str) -> Result<(&'a str, &'a str), ParseError> {
+    let end = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, rest) = s.split_at(end);
+    if number.is_empty() || number == "." || number.matches('.').count() > 1 {
+        return Err(ParseError::new(format!(
+            "expected a number at `{}` in `{}`",
+            s, src
+        )));
+    }
+    Ok((number, rest))
+}
+
+/// Multiplies the decimal `number` by `unit`, rounding down, or returns
+/// `None` if the result overflows.
+fn scale(number: &str, unit: u128) -> Option<u128> {
+    let mut parts = number.splitn(2, '.');
+    let int_part = parts.next().unwrap_or("");
+    let frac_part = parts.next().unwrap_or("");
+
+    let int_value = if int_part.is_empty() {
+        0
+    } else {
+        int_part.parse::<u128>().ok()?.checked_mul(unit)?
+    };
+    // the digits after the 18th don't change the result
+    let frac_part = &frac_part[..frac_part.len().min(18)];
+    let frac_value = if frac_part.is_empty() {
+        0
+    } else {
+        frac_part.parse::<u128>().ok()? * unit / 10_u128.pow(frac_part.len() as u32)
+    };
+    int_value.checked_add(frac_value)
+}
@@ -0,0 +1,464 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The helpers used by the code generated by `#[derive(StructOpt)]` and
+//! `#[structopt::main]`.
+//!
+//! **This is NOT PUBLIC API**.
+
+use std::ffi::OsString;
+
+use crate::{clap, ErrorFormat, ErrorReport, Failure, MainError};
+
+pub use crate::value_parser::{infer_context_parser, infer_parser, invalid_value};
+
+/// Inserts the basename of `argv[0]` as the first argument when it names
+/// a subcommand, used by `#[structopt(multicall)]`.
+pub fn multicall_args<I>(iter: I, has_subcommand: fn(&str) -> bool) -> Vec<OsString>
+where
+    I: IntoIterator,
+    I::Item: Into<OsString> + Clone,
+{
+    let mut args: Vec<OsString> = iter.into_iter().map(Into::into).collect();
+
+    let applet = args.first().and_then(|arg0| {
+        let path = std::path::Path::new(arg0);
+        // try `ls.exe` before `ls`
+        vec![path.file_name(), path.file_stem()]
+            .into_iter()
+            .flatten()
+            .filter_map(|name| name.to_str())
+            .find(|name| has_subcommand(name))
+            .map(OsString::from)
+    });
+
+    if let Some(applet) = applet {
+        args.insert(1, applet);
+    }
+    args
+}
+
+/// Prints a parse error, as text or as an [`ErrorReport`] in JSON, then exits
+/// with `code` (or the code [`clap::Error::exit`] would use), used by
+/// `#[structopt(exit_code = ...)]` and `#[structopt(error_format = ...)]`.
+pub fn exit_with_error(err: &clap::Error, format: ErrorFormat, code: Option<i32>) -> ! {
+    use std::io::Write;
+
+    let code = code.unwrap_or(if err.use_stderr() { 1 } else { 0 });
+    let format = ErrorFormat::from_env().unwrap_or(format);
+
+    if !err.use_stderr() {
+        let _ = writeln!(std::io::stdout(), "{}", err.message);
+    } else if format == ErrorFormat::Json {
+        let _ = writeln!(std::io::stderr(), "{}", ErrorReport::new(err).to_json());
+    } else {
+        let _ = writeln!(std::io::stderr(), "{}", err.message);
+    }
+    std::process::exit(code)
+}
+
+/// Prepares the `App` parsing [`std::env::args_os`], reading the `.env` file
+/// at `dotenv` (or in the current directory) with the `dotenv` feature.
+#[cfg(feature = "dotenv")]
+pub fn args_app<'a, 'b>(
+    app: clap::App<'a, 'b>,
+    dotenv: Option<&str>,
+) -> Result<clap::App<'a, 'b>, clap::Error> {
+    crate::dotenv::apply(app, dotenv.unwrap_or(".env"))
+}
+
+#[cfg(not(feature = "dotenv"))]
+pub fn args_app<'a, 'b>(
+    app: clap::App<'a, 'b>,
+    _: Option<&str>,
+) -> Result<clap::App<'a, 'b>, clap::Error> {
+    Ok(app)
+}
+
+/// Reads the environment variable `name` of a flag, given by `env_prefix`: a
+/// boolean (`true`, `yes`, `on`, `false`, `no`, `off` or empty) or a number
+/// of occurrences. Unset, it gives `0`.
+pub fn flag_env(name: &str) -> Result<u64, clap::Error> {
+    let value = match std::env::var_os(name) {
+        Some(value) => value,
+        None => return Ok(0),
+    };
+    let value = value.to_string_lossy();
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" => Ok(1),
+        "false" | "no" | "off" | "" => Ok(0),
+        number => number.parse().map_err(|_| {
+            let mut err = clap::Error::with_description(
+                &format!(
+                    "Invalid value for the environment variable '{}': expected a boolean \
+                     or a number of occurrences, found '{}'",
+                    name, value
+                ),
+                clap::ErrorKind::InvalidValue,
+            );
+            err.info = Some(vec![name.to_owned(), value.clone().into_owned()]);
+            err
+        }),
+    }
+}
+
+/// Takes the value of the environment variable of `arg` from the file named by
+/// the same variable suffixed with `_FILE`, if only the latter is set, used by
+/// `#[structopt(env_file)]`.
+pub fn env_file<'a, 'b>(arg: clap::Arg<'a, 'b>) -> clap::Arg<'a, 'b> {
+    let mut arg = arg;
+    let file_var = match &arg.v.env {
+        Some((name, None)) => {
+            let mut file_var = name.to_os_string();
+            file_var.push("_FILE");
+            file_var
+        }
+        _ => return arg,
+    };
+    let path = match std::env::var_os(&file_var) {
+        Some(path) => path,
+        None => return arg,
+    };
+
+    // an unreadable file is reported by `check_env_file`
+    if let Ok(content) = std::fs::read_to_string(&path) {
+        if let Some((_, value)) = &mut arg.v.env {
+            *value = Some(content.trim().into());
+        }
+    }
+    arg
+}
+
+/// Fails if the file named by the environment variable `var` suffixed with
+/// `_FILE` can't be read, when `var` itself isn't set, used by
+/// `#[structopt(env_file)]` when the argument `name` isn't given.
+pub fn check_env_file(name: &str, var: &str) -> Result<(), clap::Error> {
+    if std::env::var_os(var).is_some() {
+        return Ok(());
+    }
+    let file_var = format!("{}_FILE", var);
+    let path = match std::env::var_os(&file_var) {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(_) => Ok(()),
+        Err(err) => Err(clap::Error::with_description(
+            &format!(
+                "Invalid value for '{}': can't read '{}' given by {}: {}",
+                name,
+                std::path::Path::new(&path).display(),
+                file_var,
+                err
+            ),
+            clap::ErrorKind::ValueValidation,
+        )),
+    }
+}
+
+/// The message replacing the errors of the parser, `range` and `len` of a
+/// sensitive argument, which may contain its value.
+const REDACTED_ERROR: &str = "the value isn't shown, as the argument is sensitive";
+
+/// Replaces the error `message` of a sensitive argument by a fixed one.
+pub fn redact(_message: String) -> String {
+    REDACTED_ERROR.to_string()
+}
+
+/// Checks that `value`, given as `shown`, is in `range`, used by
+/// `#[structopt(range = ...)]`.
+pub fn check_range<T, R>(range: &R, value: &T, shown: &str) -> Result<(), String>
+where
+    T: PartialOrd,
+    R: std::ops::RangeBounds<T> + std::fmt::Debug,
+{
+    if range.contains(value) {
+        Ok(())
+    } else {
+        Err(format!("{} is not in {:?}", shown, range))
+    }
+}
+
+/// Checks that the length `len` of a value is in `range`, used by
+/// `#[structopt(len = ...)]`.
+pub fn check_len<R>(range: &R, len: usize) -> Result<(), String>
+where
+    R: std::ops::RangeBounds<usize> + std::fmt::Debug,
+{
+    if range.contains(&len) {
+        Ok(())
+    } else {
+        Err(format!("the length {} is not in {:?}", len, range))
+    }
+}
+
+/// The default parser of `parse(try_from_str)`, through which the derive
+/// reports a type not implementing [`FromStr`](std::str::FromStr) on the field.
+#[rustversion::attr(
+    since(1.78),
+    diagnostic::on_unimplemented(
+        message = "`{Self}` must implement `FromStr` to be parsed from an argument",
+        label = "`{Self}` doesn't implement `FromStr`",
+        note = "use `parse(...)` to give a parser for `{Self}`"
+    )
+)]
+pub trait ParseFromStr: Sized {
+    fn parse_from_str(s: &str) -> Result<Self, String>;
+}
+
+impl<T> ParseFromStr for T
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    fn parse_from_str(s: &str) -> Result<Self, String> {
+        s.parse().map_err(|e: T::Err| e.to_string())
+    }
+}
+
+/// The default parser of `parse(from_str)`, through which the derive reports
+/// a type not implementing `From<&str>` on the field.
+#[rustversion::attr(
+    since(1.78),
+    diagnostic::on_unimplemented(
+        message = "`{Self}` must implement `From<&str>` to be parsed with `parse(from_str)`",
+        label = "`{Self}` doesn't implement `From<&str>`",
+        note = "use `parse(from_str = ...)` to give a parser for `{Self}`"
+    )
+)]
+pub trait ConvertFromStr {
+    fn convert_from_str(s: &str) -> Self;
+}
+
+impl<T: for<'a> From<&'a str>> ConvertFromStr for T {
+    fn convert_from_str(s: &str) -> Self {
+        s.into()
+    }
+}
+
+/// The default parser of `parse(from_os_str)`, through which the derive
+/// reports a type not implementing `From<&OsStr>` on the field.
+#[rustversion::attr(
+    since(1.78),
+    diagnostic::on_unimplemented(
+        message = "`{Self}` must implement `From<&OsStr>` to be parsed with `parse(from_os_str)`",
+        label = "`{Self}` doesn't implement `From<&OsStr>`",
+        note = "use `parse(from_os_str = ...)` to give a parser for `{Self}`"
+    )
+)]
+pub trait ConvertFromOsStr {
+    fn convert_from_os_str(s: &std::ffi::OsStr) -> Self;
+}
+
+impl<T: for<'a> From<&'a std::ffi::OsStr>> ConvertFromOsStr for T {
+    fn convert_from_os_str(s: &std::ffi::OsStr) -> Self {
+        s.into()
+    }
+}
+
+/// Sets the `min_values` and `max_values` of `arg` from the bounds of `range`,
+/// used by `#[structopt(len = ...)]` on `Vec`s.
+pub fn values_len<'a, 'b, R>(arg: clap::Arg<'a, 'b>, range: &R) -> clap::Arg<'a, 'b>
+where
+    R: std::ops::RangeBounds<usize>,
+{
+    use std::ops::Bound::*;
+
+    let min = match range.start_bound() {
+        Included(&min) => min,
+        Excluded(&min) => min + 1,
+        Unbounded => 0,
+    };
+    let max = match range.end_bound() {
+        Included(&max) => Some(max),
+        Excluded(&max) => max.checked_sub(1),
+        Unbounded => None,
+    };
+
+    let arg = if min > 1 {
+        arg.min_values(min as u64)
+    } else {
+        arg
+    };
+    match max {
+        // clap takes `max_values(0)` as no limit, `check_values_len` rejects it
+        Some(max) if max > 0 => arg.max_values(max as u64),
+        _ => arg,
+    }
+}
+
+/// Checks that the number of values `count` of the argument `name` is in
+/// `range`, used by `#[structopt(len = ...)]` on `Vec`s.
+pub fn check_values_len<R>(name: &str, range: &R, count: usize) -> Result<(), clap::Error>
+where
+    R: std::ops::RangeBounds<usize> + std::fmt::Debug,
+{
+    if range.contains(&count) {
+        Ok(())
+    } else {
+        Err(clap::Error::with_description(
+            &format!(
+                "The argument '{}' got {} values, which is not in {:?}",
+                name, count, range
+            ),
+            clap::ErrorKind::WrongNumberOfValues,
+        ))
+    }
+}
+
+thread_local! {
+    // `const` initializers need a newer compiler than we support
+    #[allow(clippy::missing_const_for_thread_local)]
+    static INVALID_VALUE: std::cell::RefCell<Option<Option<(&'static str, String)>>> =
+        std::cell::RefCell::new(None);
+}
+
+/// Remembers the argument and value rejected by a generated validator while
+/// [`get_matches_from_safe`] runs, as clap doesn't keep them in
+/// [`clap::ErrorKind::ValueValidation`] errors. Does nothing otherwise.
+pub fn record_invalid_value(arg: &'static str, value: &std::ffi::OsStr) {
+    INVALID_VALUE.with(|cell| {
+        if let Some(slot) = cell.borrow_mut().as_mut() {
+            *slot = Some((arg, value.to_string_lossy().into_owned()));
+        }
+    });
+}
+
+/// Parses `iter` with `app` like [`clap::App::get_matches_from_safe`], filling
+/// [`clap::Error::info`] with what [`ErrorReport`] needs: the argument and
+/// value rejected by a validator, and the "did you mean" suggestions after
+/// the unknown argument, unknown subcommand or invalid value.
+pub fn get_matches_from_safe<'a, I>(
+    mut app: clap::App<'a, '_>,
+    iter: I,
+) -> Result<clap::ArgMatches<'a>, clap::Error>
+where
+    I: IntoIterator,
+    I::Item: Into<OsString> + Clone,
+{
+    let args: Vec<OsString> = iter.into_iter().map(Into::into).collect();
+
+    let outer = INVALID_VALUE.with(|cell| cell.replace(Some(None)));
+    let result = app.get_matches_from_safe_borrow(&args);
+    let invalid = INVALID_VALUE.with(|cell| cell.replace(outer)).flatten();
+
+    result.map_err(|mut err| {
+        if err.kind == clap::ErrorKind::ValueValidation {
+            if let Some((arg, value)) = invalid {
+                err.info = Some(vec![arg.to_owned(), value]);
+            }
+        } else {
+            add_suggestions(&mut err, &app, &args);
+        }
+        err
+    })
+}
+
+/// Appends the "did you mean" suggestion to the `info` of `err`, looking for
+/// candidates in the (sub)command of `app` that `args` selects.
+fn add_suggestions(err: &mut clap::Error, app: &clap::App<'_, '_>, args: &[OsString]) {
+    use clap::ErrorKind::*;
+
+    let mut app = app;
+    for arg in args.iter().skip(1).filter_map(|arg| arg.to_str()) {
+        let sub = app.p.subcommands.iter().find(|sub| {
+            sub.get_name() == arg
+                || sub
+                    .p
+                    .meta
+                    .aliases
+                    .iter()
+                    .flatten()
+                    .any(|&(alias, _)| alias == arg)
+        });
+        match sub {
+            Some(sub) => app = sub,
+            None => continue,
+        }
+    }
+
+    let info = match err.info.as_mut() {
+        Some(info) => info,
+        None => return,
+    };
+    let suggestion = match (err.kind, info.as_slice()) {
+        (UnknownArgument, [arg]) => arg.strip_prefix("--").and_then(|arg| {
+            let longs = app.p.flags.iter().filter_map(|flag| flag.s.long);
+            let longs = longs.chain(app.p.opts.iter().filter_map(|opt| opt.s.long));
+            did_you_mean(arg, longs).map(|long| format!("--{}", long))
+        }),
+        (InvalidSubcommand, [sub]) | (UnrecognizedSubcommand, [sub]) => {
+            let names = app.p.subcommands.iter().map(|sub| sub.get_name());
+            did_you_mean(sub, names).map(str::to_owned)
+        }
+        (InvalidValue, [name, value]) => {
+            let opts = app.p.opts.iter().map(|opt| (opt.b.name, &opt.v));
+            let positionals = app.p.positionals.values().map(|pos| (pos.b.name, &pos.v));
+            opts.chain(positionals)
+                .find(|(arg, _)| arg == name)
+                .and_then(|(_, v)| v.possible_vals.as_ref())
+                .and_then(|values| did_you_mean(value, values.iter().copied()))
+                .map(str::to_owned)
+        }
+        _ => None,
+    };
+    info.extend(suggestion);
+}
+
+/// The candidate most similar to `value`, as clap picks its suggestions.
+#[cfg(feature = "strsim")]
+fn did_you_mean<'a>(value: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let best = candidates
+        .map(|candidate| (strsim::jaro_winkler(value, candidate), candidate))
+        .filter(|&(confidence, _)| confidence > 0.8)
+        .fold(None, |best, (confidence, candidate)| match best {
+            Some((most, _)) if most >= confidence => best,
+            _ => Some((confidence, candidate)),
+        });
+    best.map(|(_, candidate)| candidate)
+}
+
+#[cfg(not(feature = "strsim"))]
+fn did_you_mean<'a>(_: &str, _: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    None
+}
+
+/// Reports the value returned from a `#[structopt::main]` function.
+pub trait MainResult {
+    fn report(self) -> i32;
+}
+
+impl MainResult for () {
+    fn report(self) -> i32 {
+        0
+    }
+}
+
+impl<E: MainError + std::fmt::Display> MainResult for Result<(), E> {
+    fn report(self) -> i32 {
+        match self {
+            Ok(()) => 0,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                err.exit_code()
+            }
+        }
+    }
+}
+
+impl MainResult for Result<(), Failure> {
+    fn report(self) -> i32 {
+        match self {
+            Ok(()) => 0,
+            Err(failure) => {
+                eprintln!("error: {}", failure.message);
+                failure.code
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A read-eval-print loop reading commands from lines of text.
+//!
+//! [`Repl`] reads lines from any [`BufRead`], splits them with [`split_line`]
+//! and parses them as a `T`, without any binary name. Help and parse errors
+//! are written to the output and the loop goes on with the next line, so the
+//! process never exits.
+//!
+//! Two builtins are handled before parsing:
+//!
+//! - `help [args...]` writes the help of the command (or subcommand) named by
+//!   `args`, like `args... --help` would.
+//! - `exit` ends the loop, as does the end of the input.
+//!
+//! ```
+//! use structopt::{repl::Repl, StructOpt};
+//!
+//! #[derive(StructOpt, Debug, PartialEq)]
+//! enum Command {
+//!     Push { value: i64 },
+//!     Pop,
+//! }
+//!
+//! let input = "push 1\npush two\npop\nexit\npop\n";
+//! let mut output = Vec::new();
+//! let commands: Vec<Command> = Repl::new(input.as_bytes(), &mut output)
+//!     .collect::<Result<_, _>>()
+//!     .unwrap();
+//!
+//! assert_eq!(commands, vec![Command::Push { value: 1 }, Command::Pop]);
+//! assert!(String::from_utf8(output).unwrap().contains("Invalid value"));
+//! ```
+
+use std::io::{self, BufRead, Write};
+use std::marker::PhantomData;
+
+use crate::{clap, split_line, StructOpt};
+
+/// Parses the lines of `R` as commands of type `T`, writing help, errors and
+/// the prompt to `W`. See [the module documentation](self).
+///
+/// A single [`clap::App`] is built when the `Repl` is created, and used to
+/// parse every line.
+pub struct Repl<T, R, W> {
+    app: clap::App<'static, 'static>,
+    input: R,
+    output: W,
+    prompt: Option<String>,
+    line: String,
+    _command: PhantomData<fn() -> T>,
+}
+
+impl<T: StructOpt, R: BufRead, W: Write> Repl<T, R, W> {
+    /// Creates a `Repl` reading commands from `input` and writing to `output`.
+    pub fn new(input: R, output: W) -> Self {
+        Repl {
+            app: T::clap().setting(clap::AppSettings::NoBinaryName),
+            input,
+            output,
+            prompt: None,
+            line: String::new(),
+            _command: PhantomData,
+        }
+    }
+
+    /// Sets a prompt, written before reading each line.
+    pub fn prompt(mut self, prompt: &str) -> Self {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
+
+    /// Reads lines until one of them parses as a `T`.
+    ///
+    /// Returns `Ok(None)` at the end of the input or after the `exit`
+    /// builtin, and an error only if reading or writing fails.
+    pub fn next_command(&mut self) -> io::Result<Option<T>> {
+        loop {
+            if let Some(prompt) = &self.prompt {
+                write!(self.output, "{}", prompt)?;
+                self.output.flush()?;
+            }
+
+            self.line.clear();
+            if self.input.read_line(&mut self.line)? == 0 {
+                return Ok(None);
+            }
+
+            let mut args = match split_line(&self.line) {
+                Ok(args) => args,
+                Err(err) => {
+                    writeln!(self.output, "{}", err.message)?;
+                    continue;
+                }
+            };
+
+            match args.first().map(String::as_str) {
+                None => continue,
+                Some("exit") if args.len() == 1 => return Ok(None),
+                Some("help") => {
+                    args.remove(0);
+                    args.push("--help".to_string());
+                }
+                Some(_) => {}
+            }
+
+            match self.app.get_matches_from_safe_borrow(args) {
+                Ok(matches) => match T::try_from_clap(&matches) {
+                    Ok(command) => return Ok(Some(command)),
+                    Err(err) => writeln!(self.output, "{}", err.message)?,
+                },
+                Err(err) => writeln!(self.output, "{}", err.message)?,
+            }
+        }
+    }
+
+    /// Consumes the `Repl`, returning the input and output.
+    pub fn into_inner(self) -> (R, W) {
+        (self.input, self.output)
+    }
+}
+
+impl<T: StructOpt, R: BufRead, W: Write> Iterator for Repl<T, R, W> {
+    type Item = io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_command().transpose()
+    }
+}
@@ -0,0 +1,76 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// A value that is never printed: its [`Debug`](fmt::Debug) implementation
+/// writes `***` instead of the value.
+///
+/// Arguments of type `Secret<T>` (or `Option<Secret<T>>` and
+/// `Vec<Secret<T>>`) are [sensitive](crate#sensitive-arguments): they don't
+/// show their default value nor the value of their environment variable in
+/// the help message, and the messages of their parse errors, which may
+/// contain the value, are replaced by a fixed one.
+///
+/// ```
+/// use structopt::{Secret, StructOpt};
+///
+/// #[derive(StructOpt, Debug)]
+/// struct Opt {
+///     #[structopt(long)]
+///     password: Secret<String>,
+/// }
+///
+/// let opt = Opt::from_iter(&["test", "--password", "hunter2"]);
+/// assert_eq!(opt.password.expose(), "hunter2");
+/// assert_eq!(format!("{:?}", opt), "Opt { password: *** }");
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct Secret<T>(T);
+
+impl<T> Secret<T> {
+    /// Wraps `value`.
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    /// Gives access to the value.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+
+    /// Returns the value.
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Secret(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("***")
+    }
+}
+
+impl<T: FromStr> FromStr for Secret<T> {
+    type Err = String;
+
+    /// Parses the value as a `T`, replacing its error, which may contain the
+    /// value, by a fixed message.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse()
+            .map(Secret)
+            .map_err(|_: T::Err| "the value isn't shown, as it's secret".to_string())
+    }
+}
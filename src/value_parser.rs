@@ -0,0 +1,197 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::str::FromStr;
+
+/// A parser of argument values, given by `parse(try_from_str = ...)` or
+/// `parse(try_from_os_str = ...)`.
+///
+/// It is implemented by the functions and closures taking `&I` and returning
+/// a `Result`, and can be implemented by configurable parsers:
+///
+/// ```
+/// use structopt::{StructOpt, ValueParser};
+///
+/// struct Radix(u32);
+///
+/// impl ValueParser<str> for Radix {
+///     type Value = u64;
+///     type Error = std::num::ParseIntError;
+///
+///     fn parse_value(&self, input: &str) -> Result<u64, Self::Error> {
+///         u64::from_str_radix(input, self.0)
+///     }
+/// }
+///
+/// #[derive(StructOpt, Debug)]
+/// struct Opt {
+///     #[structopt(long, parse(try_from_str = Radix(16)))]
+///     mask: u64,
+/// }
+///
+/// let opt = Opt::from_iter(&["test", "--mask", "ff"]);
+/// assert_eq!(opt.mask, 255);
+/// ```
+pub trait ValueParser<I: ?Sized> {
+    /// The parsed value.
+    type Value;
+    /// The error, shown to the user by its `Display` implementation (or by
+    /// `to_string_lossy` for `try_from_os_str`).
+    type Error;
+
+    /// Parses `input`.
+    fn parse_value(&self, input: &I) -> Result<Self::Value, Self::Error>;
+}
+
+impl<I, F, T, E> ValueParser<I> for F
+where
+    I: ?Sized,
+    F: Fn(&I) -> Result<T, E>,
+{
+    type Value = T;
+    type Error = E;
+
+    fn parse_value(&self, input: &I) -> Result<T, E> {
+        self(input)
+    }
+}
+
+/// A parser of argument values which reads the other arguments, given by
+/// `parse(try_from_str = ..., context)` or `parse(try_from_os_str = ..., context)`.
+///
+/// It is implemented by the functions and closures taking `&I` and
+/// `&ParseContext` and returning a `Result`. See
+/// [Custom string parsers](index.html#custom-string-parsers).
+pub trait ContextParser<I: ?Sized> {
+    /// The parsed value.
+    type Value;
+    /// The error, shown to the user by its `Display` implementation (or by
+    /// `to_string_lossy` for `try_from_os_str`).
+    type Error;
+
+    /// Parses `input`, the other arguments being available in `context`.
+    fn parse_with_context(
+        &self,
+        input: &I,
+        context: &ParseContext<'_>,
+    ) -> Result<Self::Value, Self::Error>;
+}
+
+impl<I, F, T, E> ContextParser<I> for F
+where
+    I: ?Sized,
+    F: Fn(&I, &ParseContext<'_>) -> Result<T, E>,
+{
+    type Value = T;
+    type Error = E;
+
+    fn parse_with_context(&self, input: &I, context: &ParseContext<'_>) -> Result<T, E> {
+        self(input, context)
+    }
+}
+
+/// The arguments of the command being parsed, given to a [`ContextParser`].
+///
+/// The arguments are referred to by their name, which is the long flag by
+/// default (without the leading `--`) or the one given by `name = "..."`.
+/// Their values are the raw strings given on the command line, or their
+/// default values.
+#[derive(Debug, Clone, Copy)]
+pub struct ParseContext<'a> {
+    matches: &'a clap::ArgMatches<'a>,
+}
+
+impl<'a> ParseContext<'a> {
+    /// **This is NOT PUBLIC API**.
+    #[doc(hidden)]
+    pub fn new(matches: &'a clap::ArgMatches<'a>) -> Self {
+        ParseContext { matches }
+    }
+
+    /// Returns whether the argument `name` was given.
+    pub fn is_present(&self, name: &str) -> bool {
+        self.matches.is_present(name)
+    }
+
+    /// Returns the value of the argument `name`, if it has one which is
+    /// valid UTF-8.
+    pub fn value_of(&self, name: &str) -> Option<&'a str> {
+        self.matches.value_of(name)
+    }
+
+    /// Returns the value of the argument `name`, if it has one.
+    pub fn value_of_os(&self, name: &str) -> Option<&'a std::ffi::OsStr> {
+        self.matches.value_of_os(name)
+    }
+
+    /// Returns the values of the argument `name`, if it has some.
+    pub fn values_of(&self, name: &str) -> Option<clap::Values<'a>> {
+        self.matches.values_of(name)
+    }
+
+    /// Parses the value of the argument `name` with [`FromStr`], returning
+    /// `None` if it has no value or the value doesn't parse.
+    ///
+    /// The parser given to that argument with `parse(...)` isn't used: call
+    /// [`ParseContext::get_with`] with it for the same value as its field.
+    pub fn get<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.value_of(name)?.parse().ok()
+    }
+
+    /// Parses the value of the argument `name` with `parser`, returning `None`
+    /// if it has no value or the value doesn't parse.
+    ///
+    /// ```
+    /// # use structopt::ParseContext;
+    /// fn parse_hex(src: &str) -> Result<u32, std::num::ParseIntError> {
+    ///     u32::from_str_radix(src, 16)
+    /// }
+    ///
+    /// fn masked(src: &str, context: &ParseContext) -> Result<u32, String> {
+    ///     let value = parse_hex(src).map_err(|e| e.to_string())?;
+    ///     Ok(value & context.get_with("mask", parse_hex).unwrap_or(!0))
+    /// }
+    /// ```
+    pub fn get_with<P: ValueParser<str>>(&self, name: &str, parser: P) -> Option<P::Value> {
+        parser.parse_value(self.value_of(name)?).ok()
+    }
+
+    /// Returns the underlying [`clap::ArgMatches`].
+    pub fn matches(&self) -> &'a clap::ArgMatches<'a> {
+        self.matches
+    }
+}
+
+/// Infers the signature of a closure given as a [`ValueParser`].
+///
+/// **This is NOT PUBLIC API**.
+#[doc(hidden)]
+pub fn infer_parser<I: ?Sized, T, F: Fn(&I) -> T>(f: F) -> F {
+    f
+}
+
+/// Infers the signature of a closure given as a [`ContextParser`].
+///
+/// **This is NOT PUBLIC API**.
+#[doc(hidden)]
+pub fn infer_context_parser<I: ?Sized, T, F: Fn(&I, &ParseContext<'_>) -> T>(f: F) -> F {
+    f
+}
+
+/// The error of `value` rejected by a [`ContextParser`] of the argument `name`.
+///
+/// **This is NOT PUBLIC API**.
+#[doc(hidden)]
+pub fn invalid_value(name: &str, value: &std::ffi::OsStr, err: &str) -> clap::Error {
+    let mut err = clap::Error::with_description(
+        &format!("Invalid value for '{}': {}", name, err),
+        clap::ErrorKind::ValueValidation,
+    );
+    err.info = Some(vec![name.to_owned(), value.to_string_lossy().into_owned()]);
+    err
+}
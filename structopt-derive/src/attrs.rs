@@ -7,16 +7,20 @@
 // except according to those terms.
 
 use crate::doc_comments::process_doc_comment;
-use crate::{parse::*, spanned::Sp, ty::Ty};
+use crate::{
+    parse::*,
+    spanned::Sp,
+    ty::{convert_type, is_duration_ty, is_os_str_ty, is_secret_ty, Ty},
+};
 
 use std::env;
 
 use heck::{ToKebabCase, ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
 use proc_macro2::{Span, TokenStream};
 use proc_macro_error::abort;
-use quote::{quote, quote_spanned, ToTokens};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::{
-    self, ext::IdentExt, spanned::Spanned, Attribute, Expr, Ident, LitStr, MetaNameValue, Type,
+    self, ext::IdentExt, spanned::Spanned, Attribute, Expr, Ident, Lit, LitStr, MetaNameValue, Type,
 };
 
 #[derive(Clone)]
@@ -38,6 +42,8 @@ pub struct Method {
 pub struct Parser {
     pub kind: Sp<ParserKind>,
     pub func: TokenStream,
+    pub context: bool,
+    has_func: bool,
 }
 
 #[derive(Debug, PartialEq, Clone)]
@@ -91,6 +97,21 @@ pub struct Attrs {
     version: Option<Method>,
     no_version: Option<Ident>,
     verbatim_doc_comment: Option<Ident>,
+    env_file: Option<Ident>,
+    sensitive: bool,
+    default_subcommand: Option<Ident>,
+    multicall: Option<Ident>,
+    run: Option<(Ident, RunSpec)>,
+    run_handler: Option<(Ident, Expr)>,
+    exit_code: Option<(Ident, Expr)>,
+    error_format: Option<(Ident, TokenStream)>,
+    dotenv: Option<(Ident, LitStr)>,
+    validate: Option<(Ident, Expr)>,
+    range: Option<(Ident, Expr)>,
+    len: Option<(Ident, Expr)>,
+    env_prefix: Option<(Ident, LitStr)>,
+    flag_env: Option<TokenStream>,
+    auto_short: Option<Ident>,
     has_custom_parser: bool,
     kind: Sp<Kind>,
 }
@@ -100,6 +121,14 @@ impl Method {
         Method { name, args }
     }
 
+    pub fn name(&self) -> &Ident {
+        &self.name
+    }
+
+    pub fn args(&self) -> &TokenStream {
+        &self.args
+    }
+
     fn from_lit_or_env(ident: Ident, lit: Option<LitStr>, env_var: &str) -> Self {
         let mut lit = match lit {
             Some(lit) => lit,
@@ -135,8 +164,77 @@ impl ToTokens for Method {
 impl Parser {
     fn default_spanned(span: Span) -> Sp<Self> {
         let kind = Sp::new(ParserKind::TryFromStr, span);
-        let func = quote_spanned!(span=> ::std::str::FromStr::from_str);
-        Sp::new(Parser { kind, func }, span)
+        let func = Self::default_func(&kind, span, None);
+        Sp::new(
+            Parser {
+                kind,
+                func,
+                context: false,
+                has_func: false,
+            },
+            span,
+        )
+    }
+
+    /// The parser used by `kind` when no function is given, parsing values of
+    /// type `ty` if known. A type it can't parse is reported at `span`.
+    fn default_func(kind: &ParserKind, span: Span, ty: Option<&Type>) -> TokenStream {
+        use ParserKind::*;
+
+        let ty = match ty {
+            Some(ty) => quote!(#ty),
+            None => quote!(_),
+        };
+        match kind {
+            FromStr => quote_spanned! { span=>
+                <#ty as ::structopt::__private::ConvertFromStr>::convert_from_str
+            },
+            TryFromStr => quote_spanned! { span=>
+                <#ty as ::structopt::__private::ParseFromStr>::parse_from_str
+            },
+            FromOsStr => quote_spanned! { span=>
+                <#ty as ::structopt::__private::ConvertFromOsStr>::convert_from_os_str
+            },
+            TryFromOsStr => abort!(span, "you must set parser for `try_from_os_str` explicitly"),
+            FromOccurrences => quote_spanned!(span=> { |v| v as _ }),
+            FromFlag => quote_spanned!(span=> ::std::convert::From::from),
+        }
+    }
+
+    /// The parser given by `func`: a function path or a closure, which is
+    /// called, or else a `ValueParser` (a `ContextParser` with `context`) for
+    /// the fallible kinds.
+    fn user_func(kind: &ParserKind, func: Expr, context: bool) -> TokenStream {
+        use ParserKind::*;
+
+        let input = match kind {
+            TryFromStr => quote!(str),
+            TryFromOsStr => quote!(::std::ffi::OsStr),
+            _ => return quote_spanned!(func.span()=> (#func)),
+        };
+        // the closures only get their signature from a `Fn` bound
+        match (&func, context) {
+            (Expr::Path(_), _) => quote!(#func),
+            (Expr::Closure(_), false) => quote_spanned! { func.span()=>
+                ::structopt::__private::infer_parser::<#input, _, _>(#func)
+            },
+            (Expr::Closure(_), true) => quote_spanned! { func.span()=>
+                ::structopt::__private::infer_context_parser::<#input, _, _>(#func)
+            },
+            // a method call, to find the parser behind a reference
+            (_, false) => quote_spanned! { func.span()=>
+                (|s: &#input| {
+                    use ::structopt::ValueParser as _;
+                    (#func).parse_value(s)
+                })
+            },
+            (_, true) => quote_spanned! { func.span()=>
+                (|s: &#input, context: &::structopt::ParseContext<'_>| {
+                    use ::structopt::ContextParser as _;
+                    (#func).parse_with_context(s, context)
+                })
+            },
+        }
     }
 
     fn from_spec(parse_ident: Ident, spec: ParserSpec) -> Sp<Self> {
@@ -152,28 +250,39 @@ impl Parser {
             s => abort!(spec.kind, "unsupported parser `{}`", s),
         };
 
+        let has_func = spec.parse_func.is_some();
+        let context = spec.context.is_some();
+        if let Some(context) = &spec.context {
+            if kind != TryFromStr && kind != TryFromOsStr {
+                abort!(
+                    context,
+                    "`context` is only allowed with `try_from_str` and `try_from_os_str`"
+                );
+            }
+            if !has_func {
+                abort!(context, "`context` requires a parser";
+                    help = "use `parse({} = parser, context)`", spec.kind);
+            }
+        }
+
         let func = match spec.parse_func {
-            None => match kind {
-                FromStr | FromOsStr => {
-                    quote_spanned!(spec.kind.span()=> ::std::convert::From::from)
-                }
-                TryFromStr => quote_spanned!(spec.kind.span()=> ::std::str::FromStr::from_str),
-                TryFromOsStr => abort!(
-                    spec.kind,
-                    "you must set parser for `try_from_os_str` explicitly"
-                ),
-                FromOccurrences => quote_spanned!(spec.kind.span()=> { |v| v as _ }),
-                FromFlag => quote_spanned!(spec.kind.span()=> ::std::convert::From::from),
-            },
+            None => Self::default_func(&kind, spec.kind.span(), None),
 
-            Some(func) => match func {
-                syn::Expr::Path(_) => quote!(#func),
-                _ => abort!(func, "`parse` argument must be a function path"),
-            },
+            Some(syn::Expr::Lit(syn::ExprLit {
+                lit: Lit::Str(lit), ..
+            })) => abort!(lit, "`parse` argument must be a parser, not a string literal";
+                help = "remove the quotes: `{} = {}`", spec.kind, lit.value()),
+
+            Some(func) => Self::user_func(&kind, func, context),
         };
 
         let kind = Sp::new(kind, spec.kind.span());
-        let parser = Parser { kind, func };
+        let parser = Parser {
+            kind,
+            func,
+            context,
+            has_func,
+        };
         Sp::new(parser, parse_ident.span())
     }
 }
@@ -236,6 +345,8 @@ impl Attrs {
             .as_ref()
             .map(|attrs| attrs.no_version.clone())
             .unwrap_or(None);
+        let env_prefix = parent_attrs.and_then(|attrs| attrs.env_prefix.clone());
+        let auto_short = parent_attrs.and_then(|attrs| attrs.auto_short.clone());
 
         Self {
             name,
@@ -250,6 +361,21 @@ impl Attrs {
             version: None,
             no_version,
             verbatim_doc_comment: None,
+            env_file: None,
+            sensitive: false,
+            default_subcommand: None,
+            multicall: None,
+            run: None,
+            run_handler: None,
+            exit_code: None,
+            error_format: None,
+            dotenv: None,
+            validate: None,
+            range: None,
+            len: None,
+            env_prefix,
+            flag_env: None,
+            auto_short,
 
             has_custom_parser: false,
             kind: Sp::new(Kind::Arg(Sp::new(Ty::Other, default_span)), default_span),
@@ -266,17 +392,130 @@ impl Attrs {
         }
     }
 
+    /// The environment variable of the field, with the `env_prefix` if any.
+    fn env_name(&self) -> TokenStream {
+        let name = self.name.clone().translate(*self.env_casing);
+        match &self.env_prefix {
+            Some((_, prefix)) => quote!(concat!(#prefix, #name)),
+            None => name,
+        }
+    }
+
+    /// Appends the allowed `range` and `len` to the help messages, or makes
+    /// them the help message if there is none.
+    fn push_constraints_help(&mut self, span: Span) {
+        let range = self
+            .range
+            .as_ref()
+            .map(|(_, range)| quote!(format!("[range: {:?}]", #range)));
+        let len = self
+            .len
+            .as_ref()
+            .map(|(_, len)| quote!(format!("[length: {:?}]", #len)));
+        let constraints = range.iter().chain(&len);
+        let constraints = quote!([#(#constraints),*].join(" "));
+
+        // clap needs a `&str` living as long as the `App`
+        let leak = |help: TokenStream| {
+            quote!({
+                ::structopt::lazy_static::lazy_static! {
+                    static ref HELP: &'static str =
+                        ::std::boxed::Box::leak(::std::string::String::into_boxed_str(#help));
+                }
+                *HELP
+            })
+        };
+
+        let mut has_help = false;
+        for method in self.doc_comment.iter_mut().chain(&mut self.methods) {
+            if method.name == "help" || method.name == "long_help" {
+                let help = &method.args;
+                method.args = leak(quote!(format!("{} {}", #help, #constraints)));
+                has_help = true;
+            }
+        }
+        if !has_help {
+            let help = leak(constraints);
+            self.methods
+                .push(Method::new(Ident::new("help", span), help));
+        }
+    }
+
     fn push_attrs(&mut self, attrs: &[Attribute]) {
         use crate::parse::StructOptAttr::*;
 
         for attr in parse_structopt_attributes(attrs) {
             match attr {
                 Short(ident) | Long(ident) => {
-                    self.push_method(ident, self.name.clone().translate(*self.casing));
+                    let name = self.name.clone().translate(*self.casing);
+                    self.push_method(ident, name);
                 }
 
                 Env(ident) => {
-                    self.push_method(ident, self.name.clone().translate(*self.env_casing));
+                    let env = self.env_name();
+                    self.push_method(ident, env);
+                }
+
+                Validate(ident, validate) => {
+                    if self.ty.is_some() {
+                        abort!(
+                            ident,
+                            "`validate` is only allowed on top of a struct, an enum \
+                             or an enum variant"
+                        );
+                    }
+                    self.validate = Some((ident, validate));
+                }
+
+                Range(ident, range) => {
+                    if self.ty.is_none() {
+                        abort!(ident, "`range` is only allowed on fields");
+                    }
+                    self.range = Some((ident, range));
+                }
+
+                Len(ident, len) => {
+                    if self.ty.is_none() {
+                        abort!(ident, "`len` is only allowed on fields");
+                    }
+                    self.len = Some((ident, len));
+                }
+
+                EnvFile(ident) => {
+                    if self.ty.is_none() {
+                        abort!(ident, "`env_file` is only allowed on fields");
+                    }
+                    self.env_file = Some(ident);
+                }
+
+                Sensitive(ident) => {
+                    if self.ty.is_none() {
+                        abort!(ident, "`sensitive` is only allowed on fields");
+                    }
+                    self.sensitive = true;
+                }
+
+                EnvPrefix(ident, prefix) => {
+                    if self.ty.is_some() {
+                        abort!(
+                            ident,
+                            "`env_prefix` is only allowed on top of a struct, an enum \
+                             or an enum variant"
+                        );
+                    }
+                    self.env_prefix = Some((ident, prefix));
+                }
+
+                AutoShort(ident) => {
+                    if self.ty.is_some() {
+                        abort!(
+                            ident,
+                            "`auto_short` is only allowed on top of a struct, an enum \
+                             or an enum variant";
+                            help = "use `short` to give a short flag to this field"
+                        );
+                    }
+                    self.auto_short = Some(ident);
                 }
 
                 Subcommand(ident) => {
@@ -303,6 +542,34 @@ impl Attrs {
 
                 VerbatimDocComment(ident) => self.verbatim_doc_comment = Some(ident),
 
+                DefaultSubcommand(ident) => self.default_subcommand = Some(ident),
+
+                Multicall(ident) => self.multicall = Some(ident),
+
+                Run(ident, spec) => self.run = Some((ident, spec)),
+
+                RunHandler(ident, handler) => self.run_handler = Some((ident, handler)),
+
+                ExitCode(ident, mapping) => self.exit_code = Some((ident, mapping)),
+
+                ErrorFormat(ident, lit) => {
+                    let format = match &*lit.value() {
+                        "json" => quote!(::structopt::ErrorFormat::Json),
+                        "text" => quote!(::structopt::ErrorFormat::Text),
+                        s => abort!(lit, "unsupported error format: `{}`", s;
+                            help = "expected `json` or `text`"),
+                    };
+                    self.error_format = Some((ident, format));
+                }
+
+                Dotenv(ident, path) => {
+                    if cfg!(not(feature = "dotenv")) {
+                        abort!(ident, "`dotenv` requires the `dotenv` feature of structopt";
+                            help = "add `features = [\"dotenv\"]` to the `structopt` dependency");
+                    }
+                    self.dotenv = Some((ident, path));
+                }
+
                 DefaultValue(ident, lit) => {
                     let val = if let Some(lit) = lit {
                         quote!(#lit)
@@ -417,6 +684,54 @@ impl Attrs {
                 "`parse` attribute is only allowed on fields"
             );
         }
+        if let Some(ident) = &res.default_subcommand {
+            if !allow_skip {
+                abort!(
+                    ident,
+                    "`default_subcommand` is only allowed on enum variants"
+                );
+            }
+        }
+        if let Some(ident) = &res.multicall {
+            if allow_skip {
+                abort!(ident, "`multicall` is only allowed on top of an enum");
+            }
+        }
+        if let Some((ident, _)) = &res.run {
+            if allow_skip {
+                abort!(ident, "`run(...)` is only allowed on top of an enum";
+                    help = "use `run = path::to::handler` to set the handler of a variant");
+            }
+        }
+        if let Some((ident, _)) = &res.run_handler {
+            if !allow_skip {
+                abort!(ident, "`run = ...` is only allowed on enum variants");
+            }
+        }
+        if let Some((ident, _)) = &res.exit_code {
+            if allow_skip {
+                abort!(
+                    ident,
+                    "`exit_code` is only allowed on top of a struct or an enum"
+                );
+            }
+        }
+        if let Some((ident, _)) = &res.error_format {
+            if allow_skip {
+                abort!(
+                    ident,
+                    "`error_format` is only allowed on top of a struct or an enum"
+                );
+            }
+        }
+        if let Some((ident, _)) = &res.dotenv {
+            if allow_skip {
+                abort!(
+                    ident,
+                    "`dotenv` is only allowed on top of a struct or an enum"
+                );
+            }
+        }
         match &*res.kind {
             Kind::Subcommand(_) => abort!(res.kind.span(), "subcommand is only allowed on fields"),
             Kind::Skip(_) if !allow_skip => {
@@ -428,11 +743,16 @@ impl Attrs {
 
     pub fn from_field(
         field: &syn::Field,
+        index: usize,
         parent_attrs: Option<&Attrs>,
         struct_casing: Sp<CasingStyle>,
         env_casing: Sp<CasingStyle>,
     ) -> Self {
-        let name = field.ident.clone().unwrap();
+        // fields of tuple structs and variants are named after their position
+        let name = match &field.ident {
+            Some(ident) => ident.clone(),
+            None => format_ident!("arg{}", index, span = field.span()),
+        };
         let mut res = Self::new(
             field.span(),
             Name::Derived(name),
@@ -444,6 +764,46 @@ impl Attrs {
         res.push_attrs(&field.attrs);
         res.push_doc_comment(&field.attrs, "help");
 
+        if let Some(ident) = &res.default_subcommand {
+            abort!(
+                ident,
+                "`default_subcommand` is only allowed on enum variants"
+            );
+        }
+        if let Some(ident) = &res.multicall {
+            abort!(ident, "`multicall` is only allowed on top of an enum");
+        }
+        if let Some((ident, _)) = &res.run {
+            abort!(
+                ident,
+                "`run` is only allowed on top of an enum or on enum variants"
+            );
+        }
+        if let Some((ident, _)) = &res.run_handler {
+            abort!(
+                ident,
+                "`run` is only allowed on top of an enum or on enum variants"
+            );
+        }
+        if let Some((ident, _)) = &res.exit_code {
+            abort!(
+                ident,
+                "`exit_code` is only allowed on top of a struct or an enum"
+            );
+        }
+        if let Some((ident, _)) = &res.error_format {
+            abort!(
+                ident,
+                "`error_format` is only allowed on top of a struct or an enum"
+            );
+        }
+        if let Some((ident, _)) = &res.dotenv {
+            abort!(
+                ident,
+                "`dotenv` is only allowed on top of a struct or an enum"
+            );
+        }
+
         match &*res.kind {
             Kind::Flatten => {
                 if res.has_custom_parser {
@@ -561,10 +921,97 @@ impl Attrs {
 
                     _ => (),
                 }
+
+                // a type the default parser can't parse is reported on the
+                // type, or on the parser if given
+                let convert_type = convert_type(*ty, &field.ty);
+                // paths and OS strings don't have to be UTF-8
+                if !res.has_custom_parser && is_os_str_ty(convert_type) {
+                    res.parser.kind = Sp::new(ParserKind::FromOsStr, convert_type.span());
+                }
+                // `Duration` doesn't implement `FromStr`
+                if !res.has_custom_parser && is_duration_ty(convert_type) {
+                    res.parser.func = quote_spanned! { convert_type.span()=>
+                        ::structopt::parsers::duration
+                    };
+                    res.parser.has_func = true;
+                }
+                if !res.parser.has_func {
+                    let span = if res.has_custom_parser {
+                        res.parser.kind.span()
+                    } else {
+                        convert_type.span()
+                    };
+                    res.parser.func =
+                        Parser::default_func(&res.parser.kind, span, Some(convert_type));
+                }
+
                 res.kind = Sp::new(Kind::Arg(ty), orig_ty.span());
             }
         }
 
+        if let Kind::Arg(ty) = &*res.kind {
+            // flags can't have an environment variable in clap 2
+            let flag = **ty == Ty::Bool
+                || *res.parser.kind == ParserKind::FromFlag
+                || *res.parser.kind == ParserKind::FromOccurrences;
+            if res.env_prefix.is_some() && !res.has_method("env") {
+                let env = res.env_name();
+                if flag {
+                    // read by the generated code, clap 2 flags can't have one
+                    res.flag_env = Some(env);
+                } else {
+                    res.push_method(Ident::new("env", field.span()), env);
+                }
+            }
+
+            if flag {
+                if let Some((ident, _)) = res.range.iter().chain(&res.len).next() {
+                    abort!(ident, "`{}` can't be used on flags", ident);
+                }
+            }
+
+            // the content of the file is a secret
+            if is_secret_ty(&field.ty) || res.env_file.is_some() {
+                res.sensitive = true;
+            }
+            if res.sensitive {
+                // clap echoes the values it rejects itself
+                let possible_values = res
+                    .methods
+                    .iter()
+                    .find(|m| m.name == "possible_values" || m.name == "possible_value");
+                if let Some(method) = possible_values {
+                    abort!(
+                        method.name,
+                        "`{}` can't be used on sensitive arguments", method.name;
+                        note = "clap prints the rejected value in its error message";
+                        help = "check the value in a `parse(try_from_str = ...)` function instead"
+                    );
+                }
+                for hide in &["hide_env_values", "hide_default_value"] {
+                    if !res.has_method(hide) {
+                        res.push_method(Ident::new(hide, field.span()), quote!(true));
+                    }
+                }
+            }
+        }
+
+        if let Some((ident, _)) = res.range.iter().chain(&res.len).next() {
+            let span = ident.span();
+            match *res.kind {
+                Kind::Arg(_) => res.push_constraints_help(span),
+                _ => abort!(ident, "`{}` is only allowed on arguments", ident),
+            }
+        }
+
+        if let Some(ident) = &res.env_file {
+            if !res.has_method("env") {
+                abort!(ident, "`env_file` requires an environment variable";
+                    help = "add `env` to the field or `env_prefix` on top of the struct");
+            }
+        }
+
         res
     }
 
@@ -579,6 +1026,108 @@ impl Attrs {
         }
     }
 
+    /// Replaces the field identifiers given to the methods relating arguments,
+    /// like `conflicts_with = output`, by the names of these arguments. Other
+    /// expressions, including identifiers of constants, are left untouched.
+    ///
+    /// `args` are the fields of the struct, with the name of the argument of
+    /// the ones that are arguments.
+    pub fn resolve_references(&mut self, args: &[(Ident, Option<TokenStream>)]) {
+        let resolve = |expr: &Expr| {
+            let ident = match expr {
+                Expr::Path(path) if path.qself.is_none() => path.path.get_ident(),
+                _ => None,
+            };
+            match ident.and_then(|ident| args.iter().find(|(field, _)| field == ident)) {
+                Some((_, Some(name))) => name.clone(),
+                Some((field, None)) => abort!(
+                    expr,
+                    "`{}` is not an argument", field;
+                    note = "only fields which are arguments can be referenced"
+                ),
+                None => quote!(#expr),
+            }
+        };
+
+        for method in &mut self.methods {
+            let name = method.name.to_string();
+            let expr = match syn::parse2::<Expr>(method.args.clone()) {
+                Ok(expr) => expr,
+                Err(_) => continue,
+            };
+            match &*name {
+                "requires" | "conflicts_with" | "required_unless" => {
+                    method.args = resolve(&expr);
+                }
+                "requires_all"
+                | "conflicts_with_all"
+                | "required_unless_all"
+                | "required_unless_one" => {
+                    let array = match &expr {
+                        Expr::Array(array) => array,
+                        Expr::Reference(reference) => match &*reference.expr {
+                            Expr::Array(array) => array,
+                            _ => continue,
+                        },
+                        _ => continue,
+                    };
+                    let names = array.elems.iter().map(resolve);
+                    method.args = quote!(&[#(#names),*]);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The short and long flags of the argument known at compile time,
+    /// formatted as `-s` and `--long`, with the span of their attribute.
+    pub fn flags(&self) -> Vec<(String, Span)> {
+        self.methods
+            .iter()
+            .filter_map(|m| {
+                let value = syn::parse2::<LitStr>(m.args.clone()).ok()?.value();
+                if m.name == "short" {
+                    let short = value.chars().next()?;
+                    Some((format!("-{}", short), m.name.span()))
+                } else if m.name == "long" {
+                    Some((format!("--{}", value), m.name.span()))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// The short flag, if known at compile time.
+    pub fn short_name(&self) -> Option<char> {
+        let short = self.find_method("short")?;
+        syn::parse2::<LitStr>(short.args.clone())
+            .ok()
+            .and_then(|short| short.value().chars().next())
+    }
+
+    /// The name of the long flag, if known at compile time.
+    pub fn long_name(&self) -> Option<String> {
+        let long = self.find_method("long")?;
+        syn::parse2::<LitStr>(long.args.clone())
+            .ok()
+            .map(|long| long.value())
+    }
+
+    /// Gives the short flag chosen by `auto_short` to the argument.
+    pub fn push_auto_short(&mut self, short: char) {
+        let span = self
+            .auto_short
+            .as_ref()
+            .map_or_else(Span::call_site, Ident::span);
+        let short = short.to_string();
+        self.push_method(Ident::new("short", span), quote_spanned!(span=> #short));
+    }
+
+    pub fn auto_short(&self) -> bool {
+        self.auto_short.is_some()
+    }
+
     pub fn has_method(&self, name: &str) -> bool {
         self.find_method(name).is_some()
     }
@@ -628,6 +1177,79 @@ impl Attrs {
         self.kind.clone()
     }
 
+    pub fn is_default_subcommand(&self) -> bool {
+        self.default_subcommand.is_some()
+    }
+
+    pub fn default_subcommand_span(&self) -> Span {
+        self.default_subcommand
+            .as_ref()
+            .map_or_else(Span::call_site, |ident| ident.span())
+    }
+
+    pub fn run(&self) -> Option<&(Ident, RunSpec)> {
+        self.run.as_ref()
+    }
+
+    pub fn run_handler(&self) -> Option<&Expr> {
+        self.run_handler.as_ref().map(|(_, handler)| handler)
+    }
+
+    pub fn exit_code(&self) -> Option<&Expr> {
+        self.exit_code.as_ref().map(|(_, mapping)| mapping)
+    }
+
+    pub fn error_format(&self) -> Option<&TokenStream> {
+        self.error_format.as_ref().map(|(_, format)| format)
+    }
+
+    pub fn dotenv(&self) -> Option<&LitStr> {
+        self.dotenv.as_ref().map(|(_, path)| path)
+    }
+
+    pub fn range(&self) -> Option<&Expr> {
+        self.range.as_ref().map(|(_, range)| range)
+    }
+
+    pub fn len(&self) -> Option<&Expr> {
+        self.len.as_ref().map(|(_, len)| len)
+    }
+
+    pub fn validate(&self) -> Option<&Expr> {
+        self.validate.as_ref().map(|(_, validate)| validate)
+    }
+
+    /// The environment variable of a field with `env_file`.
+    pub fn env_file(&self) -> Option<&TokenStream> {
+        self.env_file
+            .as_ref()
+            .and_then(|_| self.find_method("env"))
+            .map(|env| &env.args)
+    }
+
+    /// The environment variable of a flag, given by `env_prefix`.
+    pub fn flag_env(&self) -> Option<&TokenStream> {
+        self.flag_env.as_ref()
+    }
+
+    pub fn is_sensitive(&self) -> bool {
+        self.sensitive
+    }
+
+    pub fn multicall(&self) -> Option<&Ident> {
+        self.multicall.as_ref()
+    }
+
+    /// Methods registering aliases of a subcommand.
+    pub fn alias_methods(&self) -> impl Iterator<Item = &Method> {
+        self.methods.iter().filter(|m| {
+            m.name == "alias"
+                || m.name == "visible_alias"
+                || m.name == "aliases"
+                || m.name == "visible_aliases"
+        })
+    }
+
     pub fn casing(&self) -> Sp<CasingStyle> {
         self.casing.clone()
     }
@@ -19,18 +19,20 @@ extern crate proc_macro;
 
 mod attrs;
 mod doc_comments;
+mod main_fn;
 mod parse;
 mod spanned;
 mod ty;
 
 use crate::{
     attrs::{Attrs, CasingStyle, Kind, Name, ParserKind},
+    parse::{parse_structopt_attributes, StructOptAttr},
     spanned::Sp,
-    ty::{is_simple_ty, sub_type, subty_if_name, Ty},
+    ty::{convert_type, is_simple_ty, sub_type, subty_if_name, Ty},
 };
 
 use proc_macro2::{Span, TokenStream};
-use proc_macro_error::{abort, abort_call_site, proc_macro_error, set_dummy};
+use proc_macro_error::{abort, abort_call_site, emit_error, proc_macro_error, set_dummy};
 use quote::{format_ident, quote, quote_spanned};
 use syn::{punctuated::Punctuated, spanned::Spanned, token::Comma, *};
 
@@ -59,6 +61,23 @@ pub fn structopt(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     gen.into()
 }
 
+/// Generates a `fn main()` parsing the argument of the annotated function.
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn main(
+    args: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let item = match parse_macro_input!(item as Item) {
+        Item::Fn(item) => item,
+        item => abort!(
+            item,
+            "`#[structopt::main]` can only be applied to functions"
+        ),
+    };
+    main_fn::impl_main(args.into(), item).into()
+}
+
 /// Generate a block of code to add arguments/subcommands corresponding to
 /// the `fields` to an app.
 fn gen_augmentation(
@@ -66,9 +85,27 @@ fn gen_augmentation(
     app_var: &Ident,
     parent_attribute: &Attrs,
 ) -> TokenStream {
-    let mut subcmds = fields.iter().filter_map(|field| {
+    let fields_augmentation = gen_fields_augmentation(fields, app_var, parent_attribute);
+    let app_methods = parent_attribute.top_level_methods();
+    let version = parent_attribute.version();
+    quote! {{
+        let #app_var = #app_var#app_methods;
+        #fields_augmentation
+        #app_var#version
+    }}
+}
+
+/// Generate the statements adding arguments/subcommands corresponding to
+/// the `fields` to an app, without the top level methods of `parent_attribute`.
+fn gen_fields_augmentation(
+    fields: &Punctuated<Field, Comma>,
+    app_var: &Ident,
+    parent_attribute: &Attrs,
+) -> TokenStream {
+    let mut subcmds = fields.iter().enumerate().filter_map(|(index, field)| {
         let attrs = Attrs::from_field(
             field,
+            index,
             Some(parent_attribute),
             parent_attribute.casing(),
             parent_attribute.env_casing(),
@@ -83,9 +120,13 @@ fn gen_augmentation(
                 quote!()
             } else {
                 quote_spanned! { kind.span()=>
-                    let #app_var = #app_var.setting(
-                        ::structopt::clap::AppSettings::SubcommandRequiredElseHelp
-                    );
+                    let #app_var = if <#subcmd_type as ::structopt::StructOptInternal>::has_default_subcommand() {
+                        #app_var
+                    } else {
+                        #app_var.setting(
+                            ::structopt::clap::AppSettings::SubcommandRequiredElseHelp
+                        )
+                    };
                 }
             };
 
@@ -110,13 +151,41 @@ fn gen_augmentation(
         );
     }
 
-    let args = fields.iter().filter_map(|field| {
-        let attrs = Attrs::from_field(
+    check_duplicate_flags(fields, parent_attribute);
+    let auto_shorts = auto_shorts(fields, parent_attribute);
+
+    // the fields which can be referenced by `conflicts_with = field` and the like
+    let arg_names: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .filter_map(|(index, field)| {
+            let attrs = Attrs::from_field(
+                field,
+                index,
+                Some(parent_attribute),
+                parent_attribute.casing(),
+                parent_attribute.env_casing(),
+            );
+            let name = match &*attrs.kind() {
+                Kind::Arg(_) => Some(attrs.cased_name()),
+                _ => None,
+            };
+            field.ident.clone().map(|ident| (ident, name))
+        })
+        .collect();
+
+    let args = fields.iter().enumerate().filter_map(|(index, field)| {
+        let mut attrs = Attrs::from_field(
             field,
+            index,
             Some(parent_attribute),
             parent_attribute.casing(),
             parent_attribute.env_casing(),
         );
+        attrs.resolve_references(&arg_names);
+        if let Some(short) = auto_shorts[index] {
+            attrs.push_auto_short(short);
+        }
         let kind = attrs.kind();
         match &*kind {
             Kind::ExternalSubcommand => abort!(
@@ -128,7 +197,9 @@ fn gen_augmentation(
                 let ty = &field.ty;
                 Some(quote_spanned! { kind.span()=>
                     let #app_var = <#ty as ::structopt::StructOptInternal>::augment_clap(#app_var);
-                    let #app_var = if <#ty as ::structopt::StructOptInternal>::is_subcommand() {
+                    let #app_var = if <#ty as ::structopt::StructOptInternal>::is_subcommand()
+                        && !<#ty as ::structopt::StructOptInternal>::has_default_subcommand()
+                    {
                         #app_var.setting(::structopt::clap::AppSettings::SubcommandRequiredElseHelp)
                     } else {
                         #app_var
@@ -136,30 +207,110 @@ fn gen_augmentation(
                 })
             }
             Kind::Arg(ty) => {
-                let convert_type = match **ty {
-                    Ty::Vec | Ty::Option => sub_type(&field.ty).unwrap_or(&field.ty),
-                    Ty::OptionOption | Ty::OptionVec => {
-                        sub_type(&field.ty).and_then(sub_type).unwrap_or(&field.ty)
-                    }
-                    _ => &field.ty,
-                };
+                let convert_type = convert_type(**ty, &field.ty);
 
                 let occurrences = *attrs.parser().kind == ParserKind::FromOccurrences;
                 let flag = *attrs.parser().kind == ParserKind::FromFlag;
 
+                let name = attrs.cased_name();
                 let parser = attrs.parser();
                 let func = &parser.func;
+                // the errors of a sensitive argument are replaced by a fixed
+                // message, and its value isn't recorded for error reports
+                let (recorded, recorded_os) = if attrs.is_sensitive() {
+                    let redacted = quote!(::std::ffi::OsStr::new("***"));
+                    (redacted.clone(), redacted)
+                } else {
+                    (quote!(s.as_ref()), quote!(s))
+                };
+                let redact = if attrs.is_sensitive() {
+                    quote!(let e = ::structopt::__private::redact(e);)
+                } else {
+                    quote!()
+                };
+
+                // the length of a `Vec` is the number of values, checked once parsed
+                let len = match **ty {
+                    Ty::Vec | Ty::OptionVec => None,
+                    _ => attrs.len(),
+                };
+                // the context parsers and their ranges are run by `check_matches`
+                let range = attrs.range().filter(|_| !parser.context);
+                let checks = |value_str| {
+                    let len = len.map(|len| {
+                        quote_spanned! { len.span()=>
+                            ::structopt::__private::check_len(&(#len), (#value_str).chars().count())?;
+                        }
+                    });
+                    let range = range.map(|range| {
+                        quote_spanned! { range.span()=>
+                            ::structopt::__private::check_range(&(#range), &value, #value_str)?;
+                        }
+                    });
+                    (quote!(#len), quote!(#range))
+                };
+                let has_checks = len.is_some() || range.is_some();
+                let parses = !parser.context
+                    && (*parser.kind == ParserKind::TryFromStr
+                        || *parser.kind == ParserKind::TryFromOsStr);
+
                 let validator = match *parser.kind {
-                    ParserKind::TryFromStr => quote_spanned! { func.span()=>
-                        .validator(|s| {
-                            #func(s.as_str())
-                            .map(|_: #convert_type| ())
-                            .map_err(|e| e.to_string())
-                        })
-                    },
-                    ParserKind::TryFromOsStr => quote_spanned! { func.span()=>
-                        .validator_os(|s| #func(&s).map(|_: #convert_type| ()))
-                    },
+                    ParserKind::TryFromStr | ParserKind::FromStr if parses || has_checks => {
+                        let (len_check, range_check) = checks(quote!(s.as_str()));
+                        let parse = if parser.context {
+                            quote!()
+                        } else if *parser.kind == ParserKind::TryFromStr {
+                            quote! {
+                                let value: ::std::result::Result<#convert_type, _> = #func(s.as_str());
+                                let value = value.map_err(|e| e.to_string())?;
+                            }
+                        } else {
+                            quote!(let value: #convert_type = #func(s.as_str());)
+                        };
+                        quote_spanned! { func.span()=>
+                            .validator(|s| {
+                                let check = || -> ::std::result::Result<(), ::std::string::String> {
+                                    #len_check
+                                    #parse
+                                    #range_check
+                                    Ok(())
+                                };
+                                check().map_err(|e| {
+                                    #redact
+                                    ::structopt::__private::record_invalid_value(#name, #recorded);
+                                    e
+                                })
+                            })
+                        }
+                    }
+                    ParserKind::TryFromOsStr | ParserKind::FromOsStr if parses || has_checks => {
+                        let (len_check, range_check) = checks(quote!(&*s.to_string_lossy()));
+                        let parse = if parser.context {
+                            quote!()
+                        } else if *parser.kind == ParserKind::TryFromOsStr {
+                            quote! {
+                                let value: ::std::result::Result<#convert_type, _> = #func(&s);
+                                let value = value.map_err(|e| e.to_string_lossy().into_owned())?;
+                            }
+                        } else {
+                            quote!(let value: #convert_type = #func(&s);)
+                        };
+                        quote_spanned! { func.span()=>
+                            .validator_os(|s| {
+                                let check = || -> ::std::result::Result<(), ::std::string::String> {
+                                    #len_check
+                                    #parse
+                                    #range_check
+                                    Ok(())
+                                };
+                                check().map_err(|e| {
+                                    #redact
+                                    ::structopt::__private::record_invalid_value(#name, #recorded_os);
+                                    ::std::ffi::OsString::from(e)
+                                })
+                            })
+                        }
+                    }
                     _ => quote!(),
                 };
 
@@ -217,28 +368,129 @@ fn gen_augmentation(
                     }
                 };
 
-                let name = attrs.cased_name();
                 let methods = attrs.field_methods();
+                let arg = quote_spanned! { field.span()=>
+                    ::structopt::clap::Arg::with_name(#name)
+                        #modifier
+                        #methods
+                };
+                let arg = if attrs.env_file().is_some() {
+                    quote!(::structopt::__private::env_file(#arg))
+                } else {
+                    arg
+                };
+                // the number of values of a `Vec`, also checked by `validate`
+                // when the argument isn't given
+                let arg = match (attrs.len(), **ty) {
+                    (Some(len), Ty::Vec) | (Some(len), Ty::OptionVec) => {
+                        quote_spanned!(len.span()=> ::structopt::__private::values_len(#arg, &(#len)))
+                    }
+                    _ => arg,
+                };
 
                 Some(quote_spanned! { field.span()=>
-                    let #app_var = #app_var.arg(
-                        ::structopt::clap::Arg::with_name(#name)
-                            #modifier
-                            #methods
-                    );
+                    let #app_var = #app_var.arg(#arg);
                 })
             }
         }
     });
 
-    let app_methods = parent_attribute.top_level_methods();
-    let version = parent_attribute.version();
-    quote! {{
-        let #app_var = #app_var#app_methods;
+    quote! {
         #( #args )*
         #subcmd
-        #app_var#version
-    }}
+    }
+}
+
+/// Chooses the short flags given by `auto_short` to the arguments of `fields`
+/// which have a long flag and no short one: the first letter of the long flag
+/// which isn't used yet, or else the first one whose other case isn't.
+///
+/// The choices only depend on the fields before. The short flags of a
+/// `flatten`ed struct aren't known, so `auto_short` can't be used with it.
+fn auto_shorts(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs) -> Vec<Option<char>> {
+    if !parent_attribute.auto_short() {
+        return vec![None; fields.len()];
+    }
+
+    let attrs: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let attrs = Attrs::from_field(
+                field,
+                index,
+                Some(parent_attribute),
+                parent_attribute.casing(),
+                parent_attribute.env_casing(),
+            );
+            let kind = attrs.kind();
+            match &*kind {
+                Kind::Arg(_) => Some(attrs),
+                Kind::Flatten => abort!(
+                    kind.span(),
+                    "`flatten` can't be used along with `auto_short`";
+                    note = "the short flags of a flattened struct are only known at runtime, \
+                        so they could be given again";
+                    help = "set the short flags of this struct explicitly"
+                ),
+                _ => None,
+            }
+        })
+        .collect();
+
+    // `-h` and `-V` are used by `--help` and `--version`
+    let mut used = vec!['h', 'V'];
+    used.extend(attrs.iter().flatten().filter_map(Attrs::short_name));
+
+    attrs
+        .iter()
+        .map(|attrs| {
+            let attrs = attrs.as_ref()?;
+            if attrs.has_method("short") {
+                return None;
+            }
+            let long = attrs.long_name()?;
+            let letters = long.chars().filter(char::is_ascii_alphanumeric);
+            let other_case = letters.clone().map(|c| {
+                if c.is_ascii_lowercase() {
+                    c.to_ascii_uppercase()
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            });
+            let short = letters.chain(other_case).find(|c| !used.contains(c))?;
+            used.push(short);
+            Some(short)
+        })
+        .collect()
+}
+
+/// Aborts if two arguments in `fields` have the same short or long flag.
+///
+/// Only the flags known at compile time are checked, the ones of the
+/// `flatten`ed structs are checked by `StructOpt::debug_assert`.
+fn check_duplicate_flags(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs) {
+    let mut seen: Vec<(String, Span)> = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let attrs = Attrs::from_field(
+            field,
+            index,
+            Some(parent_attribute),
+            parent_attribute.casing(),
+            parent_attribute.env_casing(),
+        );
+        if let Kind::Arg(_) = &*attrs.kind() {
+            for (flag, span) in attrs.flags() {
+                if let Some((_, first)) = seen.iter().find(|(seen, _)| *seen == flag) {
+                    emit_error!(first, "`{}` is used by several arguments", flag);
+                    abort!(span, "`{}` is used by several arguments", flag;
+                        help = "set another flag with `short = \"...\"` or `long = \"...\"`"
+                    );
+                }
+                seen.push((flag, span));
+            }
+        }
+    }
 }
 
 fn gen_constructor(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs) -> TokenStream {
@@ -252,14 +504,22 @@ fn gen_constructor(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs)
     // is always the same, we factor it out.
     let matches = format_ident!("matches");
 
-    let fields = fields.iter().map(|field| {
+    let fields = fields.iter().enumerate().map(|(index, field)| {
         let attrs = Attrs::from_field(
             field,
+            index,
             Some(parent_attribute),
             parent_attribute.casing(),
             parent_attribute.env_casing(),
         );
-        let field_name = field.ident.as_ref().unwrap();
+        // `Tuple { 0: a, 1: b }` is a valid tuple constructor
+        let field_name = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index {
+                index: index as u32,
+                span: field.span(),
+            }),
+        };
         let kind = attrs.kind();
         match &*kind {
             Kind::ExternalSubcommand => abort!(
@@ -279,6 +539,11 @@ fn gen_constructor(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs)
                 quote_spanned! { kind.span()=>
                     #field_name: <#subcmd_type as ::structopt::StructOptInternal>::from_subcommand(
                         #matches.subcommand())
+                        .or_else(||
+                            <#subcmd_type as ::structopt::StructOptInternal>::from_default_subcommand(
+                                #matches
+                            )
+                        )
                         #unwrapper
                 }
             }
@@ -293,176 +558,677 @@ fn gen_constructor(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs)
             },
 
             Kind::Arg(ty) => {
-                use crate::attrs::ParserKind::*;
-
-                let parser = attrs.parser();
-                let func = &parser.func;
-                let span = parser.kind.span();
-                let (value_of, values_of, parse) = match *parser.kind {
-                    FromStr => (
-                        quote_spanned!(span=> value_of),
-                        quote_spanned!(span=> values_of),
-                        func.clone(),
-                    ),
-                    TryFromStr => (
-                        quote_spanned!(span=> value_of),
-                        quote_spanned!(span=> values_of),
-                        quote_spanned!(func.span()=> |s| #func(s).unwrap()),
-                    ),
-                    FromOsStr => (
-                        quote_spanned!(span=> value_of_os),
-                        quote_spanned!(span=> values_of_os),
-                        func.clone(),
-                    ),
-                    TryFromOsStr => (
-                        quote_spanned!(span=> value_of_os),
-                        quote_spanned!(span=> values_of_os),
-                        quote_spanned!(func.span()=> |s| #func(s).unwrap()),
-                    ),
-                    FromOccurrences => (
-                        quote_spanned!(span=> occurrences_of),
-                        quote!(),
-                        func.clone(),
-                    ),
-                    FromFlag => (quote!(), quote!(), func.clone()),
-                };
+                let field_value = gen_arg_value(field, &attrs, ty, &matches);
+                quote_spanned!(field.span()=> #field_name: #field_value )
+            }
+        }
+    });
 
-                let flag = *attrs.parser().kind == ParserKind::FromFlag;
-                let occurrences = *attrs.parser().kind == ParserKind::FromOccurrences;
-                let name = attrs.cased_name();
-                let convert_type = match **ty {
-                    Ty::Vec | Ty::Option => sub_type(&field.ty).unwrap_or(&field.ty),
-                    Ty::OptionOption | Ty::OptionVec => {
-                        sub_type(&field.ty).and_then(sub_type).unwrap_or(&field.ty)
-                    }
-                    _ => &field.ty,
-                };
-                let field_value = match **ty {
-                    Ty::Bool => quote_spanned!(ty.span()=> #matches.is_present(#name)),
+    quote! {{
+        #( #fields ),*
+    }}
+}
 
-                    Ty::Option => quote_spanned! { ty.span()=>
-                        #matches.#value_of(#name)
-                            .map(#parse)
-                    },
+/// Generate the expression reading the value of an argument field from `matches`.
+fn gen_arg_value(field: &Field, attrs: &Attrs, ty: &Sp<Ty>, matches: &Ident) -> TokenStream {
+    use crate::attrs::ParserKind::*;
+
+    let parser = attrs.parser();
+    let func = &parser.func;
+    let span = parser.kind.span();
+    // checked by `check_matches`, which `from_clap` requires
+    let try_parse = if parser.context {
+        quote_spanned! { func.span()=>
+            |s| #func(s, &::structopt::ParseContext::new(#matches))
+                .expect("the matches must be accepted by `StructOpt::check_matches`")
+        }
+    } else {
+        quote_spanned!(func.span()=> |s| #func(s).unwrap())
+    };
+    let (value_of, values_of, parse) = match *parser.kind {
+        FromStr => (
+            quote_spanned!(span=> value_of),
+            quote_spanned!(span=> values_of),
+            quote_spanned!(func.span()=> |s| #func(s)),
+        ),
+        TryFromStr => (
+            quote_spanned!(span=> value_of),
+            quote_spanned!(span=> values_of),
+            try_parse,
+        ),
+        FromOsStr => (
+            quote_spanned!(span=> value_of_os),
+            quote_spanned!(span=> values_of_os),
+            quote_spanned!(func.span()=> |s| #func(s)),
+        ),
+        TryFromOsStr => (
+            quote_spanned!(span=> value_of_os),
+            quote_spanned!(span=> values_of_os),
+            try_parse,
+        ),
+        FromOccurrences => (
+            quote_spanned!(span=> occurrences_of),
+            quote!(),
+            func.clone(),
+        ),
+        FromFlag => (quote!(), quote!(), func.clone()),
+    };
 
-                    Ty::OptionOption => quote_spanned! { ty.span()=>
-                        if #matches.is_present(#name) {
-                            Some(#matches.#value_of(#name).map(#parse))
-                        } else {
-                            None
-                        }
-                    },
+    let flag = *attrs.parser().kind == ParserKind::FromFlag;
+    let occurrences = *attrs.parser().kind == ParserKind::FromOccurrences;
+    let name = attrs.cased_name();
+    let convert_type = convert_type(**ty, &field.ty);
+    // an invalid variable is reported by `check_matches`
+    let (is_present, occurrences_of) = match attrs.flag_env() {
+        Some(env) => (
+            quote!((#matches.is_present(#name) || ::structopt::__private::flag_env(#env).unwrap_or(0) > 0)),
+            quote! {
+                match #matches.occurrences_of(#name) {
+                    0 => ::structopt::__private::flag_env(#env).unwrap_or(0),
+                    n => n,
+                }
+            },
+        ),
+        None => (
+            quote!(#matches.is_present(#name)),
+            quote!(#matches.#value_of(#name)),
+        ),
+    };
+    match **ty {
+        Ty::Bool => quote_spanned!(ty.span()=> #is_present),
 
-                    Ty::OptionVec => quote_spanned! { ty.span()=>
-                        if #matches.is_present(#name) {
-                            Some(#matches.#values_of(#name)
-                                 .map_or_else(Vec::new, |v| v.map::<#convert_type, _>(#parse).collect()))
-                        } else {
-                            None
-                        }
-                    },
+        Ty::Option => quote_spanned! { ty.span()=>
+            #matches.#value_of(#name)
+                .map(#parse)
+        },
 
-                    Ty::Vec => quote_spanned! { ty.span()=>
-                        #matches.#values_of(#name)
-                            .map_or_else(Vec::new, |v| v.map::<#convert_type, _>(#parse).collect())
-                    },
+        Ty::OptionOption => quote_spanned! { ty.span()=>
+            if #matches.is_present(#name) {
+                Some(#matches.#value_of(#name).map(#parse))
+            } else {
+                None
+            }
+        },
 
-                    Ty::Other if occurrences => quote_spanned! { ty.span()=>
-                        #parse(#matches.#value_of(#name))
-                    },
+        Ty::OptionVec => quote_spanned! { ty.span()=>
+            if #matches.is_present(#name) {
+                Some(#matches.#values_of(#name)
+                     .map_or_else(Vec::new, |v| v.map::<#convert_type, _>(#parse).collect()))
+            } else {
+                None
+            }
+        },
 
-                    Ty::Other if flag => quote_spanned! { ty.span()=>
-                        #parse(#matches.is_present(#name))
-                    },
+        Ty::Vec => quote_spanned! { ty.span()=>
+            #matches.#values_of(#name)
+                .map_or_else(Vec::new, |v| v.map::<#convert_type, _>(#parse).collect())
+        },
 
-                    Ty::Other => quote_spanned! { ty.span()=>
-                        #matches.#value_of(#name)
-                            .map(#parse)
-                            .unwrap()
-                    },
-                };
+        Ty::Other if occurrences => quote_spanned! { ty.span()=>
+            #parse(#occurrences_of)
+        },
 
-                quote_spanned!(field.span()=> #field_name: #field_value )
-            }
-        }
-    });
+        Ty::Other if flag => quote_spanned! { ty.span()=>
+            #parse(#is_present)
+        },
 
-    quote! {{
-        #( #fields ),*
-    }}
+        Ty::Other => quote_spanned! { ty.span()=>
+            #matches.#value_of(#name)
+                .map(#parse)
+                .unwrap()
+        },
+    }
 }
 
-fn gen_from_clap(
-    struct_name: &Ident,
+/// Generate the statements overwriting the fields whose arguments are present
+/// in `matches`, leaving the others untouched.
+///
+/// With `bind_fields`, the fields are accessed through bindings of an enum
+/// variant pattern, returned as the first element.
+fn gen_updater(
     fields: &Punctuated<Field, Comma>,
     parent_attribute: &Attrs,
-) -> TokenStream {
-    let field_block = gen_constructor(fields, parent_attribute);
-
-    quote! {
-        fn from_clap(matches: &::structopt::clap::ArgMatches) -> Self {
-            #struct_name #field_block
-        }
-    }
-}
+    bind_fields: bool,
+) -> (TokenStream, TokenStream) {
+    let matches = format_ident!("matches");
 
-fn gen_clap(attrs: &[Attribute]) -> GenOutput {
-    let name = std::env::var("CARGO_PKG_NAME").ok().unwrap_or_default();
+    let (bindings, updates): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .enumerate()
+        .filter_map(|(index, field)| {
+            let attrs = Attrs::from_field(
+                field,
+                index,
+                Some(parent_attribute),
+                parent_attribute.casing(),
+                parent_attribute.env_casing(),
+            );
+            let field_name = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index {
+                    index: index as u32,
+                    span: field.span(),
+                }),
+            };
+            let binding = format_ident!("__structopt_field_{}", index);
+            let (pattern, place) = if bind_fields {
+                (quote!(#field_name: #binding,), quote!(*#binding))
+            } else {
+                (quote!(), quote!(self.#field_name))
+            };
 
-    let attrs = Attrs::from_struct(
-        Span::call_site(),
-        attrs,
-        Name::Assigned(quote!(#name)),
-        None,
-        Sp::call_site(DEFAULT_CASING),
-        Sp::call_site(DEFAULT_ENV_CASING),
-        false,
-    );
-    let tokens = {
-        let name = attrs.cased_name();
-        quote!(::structopt::clap::App::new(#name))
-    };
+            let kind = attrs.kind();
+            let update = match &*kind {
+                Kind::ExternalSubcommand | Kind::Skip(_) => return None,
 
-    GenOutput { tokens, attrs }
-}
+                Kind::Subcommand(ty) => {
+                    let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                        (Ty::Option, Some(sub_type)) => sub_type,
+                        _ => &field.ty,
+                    };
+                    let update_subcommand = |subcmd| {
+                        quote_spanned! { kind.span()=>
+                            if !<#subcmd_type as ::structopt::StructOptInternal>::update_from_subcommand(
+                                #subcmd,
+                                #matches.subcommand(),
+                            ) {
+                                <#subcmd_type as ::structopt::StructOptInternal>::update_from_default_subcommand(
+                                    #subcmd,
+                                    #matches,
+                                );
+                            }
+                        }
+                    };
+                    match **ty {
+                        Ty::Option => {
+                            let update = update_subcommand(quote!(subcmd));
+                            quote_spanned! { kind.span()=>
+                                match &mut #place {
+                                    Some(subcmd) => { #update }
+                                    None => {
+                                        #place = <#subcmd_type as ::structopt::StructOptInternal>::from_subcommand(
+                                            #matches.subcommand()
+                                        );
+                                    }
+                                }
+                            }
+                        }
+                        _ => update_subcommand(quote!(&mut #place)),
+                    }
+                }
 
-fn gen_clap_struct(struct_attrs: &[Attribute]) -> GenOutput {
-    let initial_clap_app_gen = gen_clap(struct_attrs);
-    let clap_tokens = initial_clap_app_gen.tokens;
+                Kind::Flatten => quote_spanned! { kind.span()=>
+                    ::structopt::StructOpt::update_from_clap(&mut #place, #matches);
+                },
 
-    let augmented_tokens = quote! {
-        fn clap<'a, 'b>() -> ::structopt::clap::App<'a, 'b> {
-            let app = #clap_tokens;
-            <Self as ::structopt::StructOptInternal>::augment_clap(app)
-        }
-    };
+                Kind::Arg(ty) => {
+                    let name = attrs.cased_name();
+                    let field_value = gen_arg_value(field, &attrs, ty, &matches);
+                    quote_spanned! { field.span()=>
+                        if #matches.occurrences_of(#name) > 0 {
+                            #place = #field_value;
+                        }
+                    }
+                }
+            };
+            Some((pattern, update))
+        })
+        .unzip();
 
-    GenOutput {
-        tokens: augmented_tokens,
-        attrs: initial_clap_app_gen.attrs,
-    }
+    (quote!( #(#bindings)* ), quote!( #(#updates)* ))
 }
 
-fn gen_augment_clap(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs) -> TokenStream {
-    let app_var = Ident::new("app", Span::call_site());
-    let augmentation = gen_augmentation(fields, &app_var, parent_attribute);
+fn gen_update_from_clap(
+    fields: &Punctuated<Field, Comma>,
+    parent_attribute: &Attrs,
+) -> TokenStream {
+    let (_, updates) = gen_updater(fields, parent_attribute, false);
+
     quote! {
-        fn augment_clap<'a, 'b>(
-            #app_var: ::structopt::clap::App<'a, 'b>
-        ) -> ::structopt::clap::App<'a, 'b> {
-            #augmentation
+        fn update_from_clap(&mut self, matches: &::structopt::clap::ArgMatches) {
+            #updates
         }
     }
 }
 
-fn gen_clap_enum(enum_attrs: &[Attribute]) -> GenOutput {
-    let initial_clap_app_gen = gen_clap(enum_attrs);
-    let clap_tokens = initial_clap_app_gen.tokens;
+/// Generate the statements validating the flattened structs, the subcommands
+/// and the number of values of `Vec`s with a `len` among `fields`.
+///
+/// With `bind_fields`, the fields are accessed through bindings of an enum
+/// variant pattern, returned as the first element.
+fn gen_nested_validation(
+    fields: &Punctuated<Field, Comma>,
+    parent_attribute: &Attrs,
+    bind_fields: bool,
+) -> (TokenStream, TokenStream) {
+    let (bindings, checks): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .enumerate()
+        .filter_map(|(index, field)| {
+            let attrs = Attrs::from_field(
+                field,
+                index,
+                Some(parent_attribute),
+                parent_attribute.casing(),
+                parent_attribute.env_casing(),
+            );
+            let field_name = match &field.ident {
+                Some(ident) => Member::Named(ident.clone()),
+                None => Member::Unnamed(Index {
+                    index: index as u32,
+                    span: field.span(),
+                }),
+            };
+            let binding = format_ident!("__structopt_field_{}", index);
+            let (pattern, place) = if bind_fields {
+                (quote!(#field_name: #binding,), quote!(*#binding))
+            } else {
+                (quote!(), quote!(self.#field_name))
+            };
+
+            let kind = attrs.kind();
+            let check = match &*kind {
+                Kind::Subcommand(ty) if **ty == Ty::Option => quote_spanned! { kind.span()=>
+                    if let Some(subcmd) = &#place {
+                        ::structopt::StructOpt::validate(subcmd)?;
+                    }
+                },
+                Kind::Subcommand(_) | Kind::Flatten => quote_spanned! { kind.span()=>
+                    ::structopt::StructOpt::validate(&#place)?;
+                },
+                Kind::Arg(ty) => {
+                    let name = attrs.cased_name();
+                    match (attrs.len(), **ty) {
+                        (Some(len), Ty::Vec) => quote_spanned! { len.span()=>
+                            ::structopt::__private::check_values_len(#name, &(#len), (#place).len())?;
+                        },
+                        (Some(len), Ty::OptionVec) => quote_spanned! { len.span()=>
+                            if let Some(values) = &#place {
+                                ::structopt::__private::check_values_len(#name, &(#len), values.len())?;
+                            }
+                        },
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            };
+            Some((pattern, check))
+        })
+        .unzip();
+
+    (quote!( #(#bindings)* ), quote!( #(#checks)* ))
+}
+
+/// Generate the call of the `validate` function of `attrs`, if any.
+fn gen_own_validation(attrs: &Attrs) -> TokenStream {
+    match attrs.validate() {
+        Some(validate) => quote_spanned! { validate.span()=>
+            (#validate)(self).map_err(|message: String| {
+                ::structopt::clap::Error::with_description(
+                    &message,
+                    ::structopt::clap::ErrorKind::ValueValidation,
+                )
+            })?;
+        },
+        None => quote!(),
+    }
+}
+
+fn gen_validate(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs) -> TokenStream {
+    let (_, checks) = gen_nested_validation(fields, parent_attribute, false);
+    let validate = gen_own_validation(parent_attribute);
+
+    quote! {
+        fn validate(&self) -> ::std::result::Result<(), ::structopt::clap::Error> {
+            #checks
+            #validate
+            Ok(())
+        }
+    }
+}
+
+fn gen_validate_enum(
+    name: &Ident,
+    variants: &Punctuated<Variant, Comma>,
+    parent_attribute: &Attrs,
+) -> TokenStream {
+    use syn::Fields::*;
+
+    let match_arms = variants.iter().filter_map(|variant| {
+        let attrs = Attrs::from_struct(
+            variant.span(),
+            &variant.attrs,
+            Name::Derived(variant.ident.clone()),
+            Some(parent_attribute),
+            parent_attribute.casing(),
+            parent_attribute.env_casing(),
+            true,
+        );
+        if let Kind::ExternalSubcommand | Kind::Skip(_) = &*attrs.kind() {
+            return None;
+        }
+
+        let variant_name = &variant.ident;
+        let (pattern, checks) = match variant.fields {
+            Named(ref fields) => {
+                let (bindings, checks) = gen_nested_validation(&fields.named, &attrs, true);
+                (quote!({ #bindings .. }), checks)
+            }
+            Unit => (quote!(), quote!()),
+            Unnamed(ref fields) if fields.unnamed.len() == 1 => (
+                quote!((inner)),
+                quote!(::structopt::StructOpt::validate(inner)?;),
+            ),
+            Unnamed(ref fields) => {
+                let (bindings, checks) = gen_nested_validation(&fields.unnamed, &attrs, true);
+                (quote!({ #bindings .. }), checks)
+            }
+        };
+        let validate = gen_own_validation(&attrs);
+
+        Some(quote! {
+            #name :: #variant_name #pattern => {
+                #checks
+                #validate
+            }
+        })
+    });
+    let validate = gen_own_validation(parent_attribute);
+
+    quote! {
+        #[allow(unreachable_patterns)]
+        fn validate(&self) -> ::std::result::Result<(), ::structopt::clap::Error> {
+            match self {
+                #( #match_arms )*
+                _ => {}
+            }
+            #validate
+            Ok(())
+        }
+    }
+}
+
+/// Generate the statements running the context parsers of `fields` on
+/// `matches`, and checking the flattened structs and the subcommands.
+fn gen_matches_checks(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs) -> TokenStream {
+    let matches = format_ident!("matches");
+
+    let checks = fields.iter().enumerate().filter_map(|(index, field)| {
+        let attrs = Attrs::from_field(
+            field,
+            index,
+            Some(parent_attribute),
+            parent_attribute.casing(),
+            parent_attribute.env_casing(),
+        );
+        let kind = attrs.kind();
+        // the file is only read when the value would come from it
+        let env_file = attrs.env_file().map(|env| {
+            let name = attrs.cased_name();
+            quote! {
+                if #matches.occurrences_of(#name) == 0 {
+                    ::structopt::__private::check_env_file(#name, #env)?;
+                }
+            }
+        });
+        let check = match &*kind {
+            Kind::Subcommand(ty) => {
+                let subcmd_type = match (**ty, sub_type(&field.ty)) {
+                    (Ty::Option, Some(sub_type)) => sub_type,
+                    _ => &field.ty,
+                };
+                Some(quote_spanned! { kind.span()=>
+                    <#subcmd_type as ::structopt::StructOpt>::check_matches(#matches)?;
+                })
+            }
+
+            Kind::Flatten => {
+                let ty = &field.ty;
+                Some(quote_spanned! { kind.span()=>
+                    <#ty as ::structopt::StructOpt>::check_matches(#matches)?;
+                })
+            }
+
+            Kind::Arg(_) if attrs.flag_env().is_some() => {
+                let name = attrs.cased_name();
+                let env = attrs.flag_env();
+                Some(quote! {
+                    if !#matches.is_present(#name) {
+                        ::structopt::__private::flag_env(#env)?;
+                    }
+                })
+            }
+
+            Kind::Arg(ty) if attrs.parser().context => {
+                let parser = attrs.parser();
+                let func = &parser.func;
+                let name = attrs.cased_name();
+                let convert_type = convert_type(**ty, &field.ty);
+                let (values_of, shown, error, recorded) = match *parser.kind {
+                    ParserKind::TryFromStr => (
+                        quote!(values_of),
+                        quote!(s),
+                        quote!(e.to_string()),
+                        quote!(::std::ffi::OsStr::new(s)),
+                    ),
+                    _ => (
+                        quote!(values_of_os),
+                        quote!(&*s.to_string_lossy()),
+                        quote!(e.to_string_lossy().into_owned()),
+                        quote!(s),
+                    ),
+                };
+                let (recorded, redact) = if attrs.is_sensitive() {
+                    (
+                        quote!(::std::ffi::OsStr::new("***")),
+                        quote!(let e = ::structopt::__private::redact(e);),
+                    )
+                } else {
+                    (recorded, quote!())
+                };
+                let range_check = attrs.range().map(|range| {
+                    quote_spanned! { range.span()=>
+                        ::structopt::__private::check_range(&(#range), &value, #shown)?;
+                    }
+                });
+
+                Some(quote_spanned! { func.span()=>
+                    for s in #matches.#values_of(#name).into_iter().flatten() {
+                        let context = ::structopt::ParseContext::new(#matches);
+                        let check = || -> ::std::result::Result<(), ::std::string::String> {
+                            let value: ::std::result::Result<#convert_type, _> = #func(s, &context);
+                            let value = value.map_err(|e| #error)?;
+                            #range_check
+                            Ok(())
+                        };
+                        check().map_err(|e| {
+                            #redact
+                            ::structopt::__private::invalid_value(#name, #recorded, &e)
+                        })?;
+                    }
+                })
+            }
+
+            _ => None,
+        };
+        if env_file.is_none() && check.is_none() {
+            None
+        } else {
+            Some(quote!(#env_file #check))
+        }
+    });
+
+    quote!( #( #checks )* )
+}
+
+fn gen_check_matches(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs) -> TokenStream {
+    let checks = gen_matches_checks(fields, parent_attribute);
+
+    quote! {
+        fn check_matches(
+            matches: &::structopt::clap::ArgMatches
+        ) -> ::std::result::Result<(), ::structopt::clap::Error> {
+            #checks
+            Ok(())
+        }
+    }
+}
+
+fn gen_check_matches_enum(
+    variants: &Punctuated<Variant, Comma>,
+    parent_attribute: &Attrs,
+) -> TokenStream {
+    use syn::Fields::*;
+
+    let matches = format_ident!("matches");
+
+    let variant_checks = |variant: &Variant, attrs: &Attrs| match variant.fields {
+        Named(ref fields) => gen_matches_checks(&fields.named, attrs),
+        Unit => quote!(),
+        Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+            let ty = &fields.unnamed[0].ty;
+            quote!( <#ty as ::structopt::StructOpt>::check_matches(#matches)?; )
+        }
+        Unnamed(ref fields) => gen_matches_checks(&fields.unnamed, attrs),
+    };
+
+    // the default subcommand reads the arguments of its parent when none is given
+    let default = find_default_subcommand(variants, parent_attribute).map(|(variant, attrs)| {
+        let checks = variant_checks(variant, &attrs);
+        quote! {
+            if #matches.subcommand_name().is_none() {
+                #checks
+            }
+        }
+    });
+
+    let (flatten_variants, variants): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .filter_map(|variant| {
+            let attrs = Attrs::from_struct(
+                variant.span(),
+                &variant.attrs,
+                Name::Derived(variant.ident.clone()),
+                Some(parent_attribute),
+                parent_attribute.casing(),
+                parent_attribute.env_casing(),
+                true,
+            );
+            match &*attrs.kind() {
+                Kind::ExternalSubcommand | Kind::Skip(_) => None,
+                _ => Some((variant, attrs)),
+            }
+        })
+        .partition(|(_, attrs)| match &*attrs.kind() {
+            Kind::Flatten => true,
+            _ => false,
+        });
+
+    let match_arms = variants.iter().map(|(variant, attrs)| {
+        let sub_name = attrs.cased_name();
+        let checks = variant_checks(variant, attrs);
+        quote! {
+            (#sub_name, Some(#matches)) => {
+                #checks
+            }
+        }
+    });
+    // a flattened enum finds its own subcommands
+    let child_subcommands = flatten_variants
+        .iter()
+        .map(|(variant, attrs)| variant_checks(variant, attrs));
+
+    quote! {
+        #[allow(unreachable_patterns)]
+        fn check_matches(
+            #matches: &::structopt::clap::ArgMatches
+        ) -> ::std::result::Result<(), ::structopt::clap::Error> {
+            match #matches.subcommand() {
+                #( #match_arms )*
+                _ => {}
+            }
+            #( #child_subcommands )*
+            #default
+            Ok(())
+        }
+    }
+}
+
+fn gen_from_clap(
+    struct_name: &Ident,
+    fields: &Punctuated<Field, Comma>,
+    parent_attribute: &Attrs,
+) -> TokenStream {
+    let field_block = gen_constructor(fields, parent_attribute);
+
+    quote! {
+        fn from_clap(matches: &::structopt::clap::ArgMatches) -> Self {
+            #struct_name #field_block
+        }
+    }
+}
+
+fn gen_clap(attrs: &[Attribute]) -> GenOutput {
+    let name = std::env::var("CARGO_PKG_NAME").ok().unwrap_or_default();
+
+    let attrs = Attrs::from_struct(
+        Span::call_site(),
+        attrs,
+        Name::Assigned(quote!(#name)),
+        None,
+        Sp::call_site(DEFAULT_CASING),
+        Sp::call_site(DEFAULT_ENV_CASING),
+        false,
+    );
+    let tokens = {
+        let name = attrs.cased_name();
+        quote!(::structopt::clap::App::new(#name))
+    };
+
+    GenOutput { tokens, attrs }
+}
+
+fn gen_clap_struct(struct_attrs: &[Attribute]) -> GenOutput {
+    let initial_clap_app_gen = gen_clap(struct_attrs);
+    let clap_tokens = initial_clap_app_gen.tokens;
+
+    let augmented_tokens = quote! {
+        fn clap<'a, 'b>() -> ::structopt::clap::App<'a, 'b> {
+            let app = #clap_tokens;
+            <Self as ::structopt::StructOptInternal>::augment_clap(app)
+        }
+    };
+
+    GenOutput {
+        tokens: augmented_tokens,
+        attrs: initial_clap_app_gen.attrs,
+    }
+}
+
+fn gen_augment_clap(fields: &Punctuated<Field, Comma>, parent_attribute: &Attrs) -> TokenStream {
+    let app_var = Ident::new("app", Span::call_site());
+    let augmentation = gen_augmentation(fields, &app_var, parent_attribute);
+    quote! {
+        fn augment_clap<'a, 'b>(
+            #app_var: ::structopt::clap::App<'a, 'b>
+        ) -> ::structopt::clap::App<'a, 'b> {
+            #augmentation
+        }
+    }
+}
+
+fn gen_clap_enum(enum_attrs: &[Attribute]) -> GenOutput {
+    let initial_clap_app_gen = gen_clap(enum_attrs);
+    let clap_tokens = initial_clap_app_gen.tokens;
 
     let tokens = quote! {
         fn clap<'a, 'b>() -> ::structopt::clap::App<'a, 'b> {
-            let app = #clap_tokens
-                .setting(::structopt::clap::AppSettings::SubcommandRequiredElseHelp);
+            let app = #clap_tokens;
+            let app = if <Self as ::structopt::StructOptInternal>::has_default_subcommand() {
+                app
+            } else {
+                app.setting(::structopt::clap::AppSettings::SubcommandRequiredElseHelp)
+            };
             <Self as ::structopt::StructOptInternal>::augment_clap(app)
         }
     };
@@ -516,66 +1282,431 @@ fn gen_augment_clap_enum(
                         "`flatten` is usable only with single-typed tuple variants"
                     ),
                 }
-            },
+            },
+
+            _ => {
+                let app_var = Ident::new("subcommand", Span::call_site());
+                let from_attrs = attrs.top_level_methods();
+                let version = attrs.version();
+
+                let arg_block = match variant.fields {
+                    // If the variant is named, then gen_augmentation already generates the
+                    // top level methods (#from_attrs) and version.
+                    Named(ref fields) => gen_augmentation(&fields.named, &app_var, &attrs),
+                    Unit => quote!( #app_var#from_attrs#version ),
+                    Unnamed(FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 1 => {
+                        let ty = &unnamed[0];
+                        quote_spanned! { ty.span()=>
+                            {
+                                let #app_var = <#ty as ::structopt::StructOptInternal>::augment_clap(
+                                    #app_var
+                                );
+                                if <#ty as ::structopt::StructOptInternal>::is_subcommand()
+                                    && !<#ty as ::structopt::StructOptInternal>::has_default_subcommand()
+                                {
+                                    #app_var.setting(
+                                        ::structopt::clap::AppSettings::SubcommandRequiredElseHelp
+                                    )
+                                } else {
+                                    #app_var
+                                }#from_attrs#version
+                            }
+                        }
+                    }
+                    Unnamed(ref fields) => gen_augmentation(&fields.unnamed, &app_var, &attrs),
+                };
+
+                let name = attrs.cased_name();
+                Some(quote! {
+                    let app = app.subcommand({
+                        let #app_var = ::structopt::clap::SubCommand::with_name(#name);
+                        #arg_block
+                    });
+                })
+            },
+        }
+    });
+
+    let default_subcommand = match find_default_subcommand(variants, parent_attribute) {
+        Some((variant, attrs)) => {
+            let app_var = Ident::new("app", Span::call_site());
+            let arg_block = match variant.fields {
+                Named(ref fields) => gen_fields_augmentation(&fields.named, &app_var, &attrs),
+                Unit => quote!(),
+                Unnamed(FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 1 => {
+                    let ty = &unnamed[0];
+                    quote_spanned! { ty.span()=>
+                        let #app_var = <#ty as ::structopt::StructOptInternal>::augment_clap(
+                            #app_var
+                        );
+                    }
+                }
+                Unnamed(ref fields) => gen_fields_augmentation(&fields.unnamed, &app_var, &attrs),
+            };
+
+            quote! {
+                #arg_block
+                let #app_var = #app_var.setting(
+                    ::structopt::clap::AppSettings::SubcommandsNegateReqs
+                );
+            }
+        }
+        None => quote!(),
+    };
+
+    let app_methods = parent_attribute.top_level_methods();
+    let version = parent_attribute.version();
+    quote! {
+        fn augment_clap<'a, 'b>(
+            app: ::structopt::clap::App<'a, 'b>
+        ) -> ::structopt::clap::App<'a, 'b> {
+            #default_subcommand
+            let app = app #app_methods;
+            #( #subcommands )*;
+            app #version
+        }
+    }
+}
+
+/// Find the variant marked with `default_subcommand`, if any.
+fn find_default_subcommand<'a>(
+    variants: &'a Punctuated<Variant, Comma>,
+    parent_attribute: &Attrs,
+) -> Option<(&'a Variant, Attrs)> {
+    let mut defaults = variants.iter().filter_map(|variant| {
+        let attrs = Attrs::from_struct(
+            variant.span(),
+            &variant.attrs,
+            Name::Derived(variant.ident.clone()),
+            Some(parent_attribute),
+            parent_attribute.casing(),
+            parent_attribute.env_casing(),
+            true,
+        );
+
+        if !attrs.is_default_subcommand() {
+            return None;
+        }
+
+        match &*attrs.kind() {
+            Kind::Flatten | Kind::ExternalSubcommand | Kind::Skip(_) => abort!(
+                attrs.default_subcommand_span(),
+                "`default_subcommand` cannot be used with `flatten`, \
+                 `external_subcommand` or `skip`"
+            ),
+            _ => Some((variant, attrs)),
+        }
+    });
+
+    let default = defaults.next();
+    if let Some((_, attrs)) = defaults.next() {
+        abort!(
+            attrs.default_subcommand_span(),
+            "Only one variant can be marked with `default_subcommand`, \
+             this is the second"
+        );
+    }
+    default
+}
+
+fn gen_has_subcommand(
+    variants: &Punctuated<Variant, Comma>,
+    parent_attribute: &Attrs,
+) -> TokenStream {
+    use syn::Fields::*;
+
+    let name = format_ident!("name");
+
+    let checks = variants.iter().filter_map(|variant| {
+        let attrs = Attrs::from_struct(
+            variant.span(),
+            &variant.attrs,
+            Name::Derived(variant.ident.clone()),
+            Some(parent_attribute),
+            parent_attribute.casing(),
+            parent_attribute.env_casing(),
+            true,
+        );
+
+        match &*attrs.kind() {
+            Kind::Skip(_) | Kind::ExternalSubcommand => None,
+
+            Kind::Flatten => match variant.fields {
+                Unnamed(FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 1 => {
+                    let ty = &unnamed[0];
+                    Some(quote! {
+                        <#ty as ::structopt::StructOptInternal>::has_subcommand(#name)
+                    })
+                }
+                _ => abort!(
+                    variant,
+                    "`flatten` is usable only with single-typed tuple variants"
+                ),
+            },
+
+            _ => {
+                let sub_name = attrs.cased_name();
+                let aliases = attrs.alias_methods().map(|m| {
+                    let args = m.args();
+                    if m.name() == "alias" || m.name() == "visible_alias" {
+                        quote!(|| #name == #args)
+                    } else {
+                        quote!(|| (#args).iter().any(|alias| *alias == #name))
+                    }
+                });
+                Some(quote!( (#name == #sub_name #( #aliases )*) ))
+            }
+        }
+    });
+
+    quote! {
+        fn has_subcommand(#name: &str) -> bool {
+            false #( || #checks )*
+        }
+    }
+}
+
+/// Generate `StructOpt` methods overriding how the command line is parsed:
+/// with `multicall`, the basename of `argv[0]` is fed as the subcommand when
+/// it names one, with `exit_code` and `error_format`, parse errors are
+/// reported with a custom exit code and format, and with `dotenv`, another
+/// `.env` file is read.
+fn gen_from_args(attrs: &Attrs) -> TokenStream {
+    let prepare_args = attrs.multicall().map(|_| {
+        quote! {
+            fn prepare_args(
+                args: ::std::vec::Vec<::std::ffi::OsString>,
+            ) -> ::std::vec::Vec<::std::ffi::OsString> {
+                ::structopt::__private::multicall_args(
+                    args,
+                    <Self as ::structopt::StructOptInternal>::has_subcommand,
+                )
+            }
+        }
+    });
+
+    let exit_on_error = if attrs.exit_code().is_some() || attrs.error_format().is_some() {
+        let code = match attrs.exit_code() {
+            Some(exit_code) => quote_spanned!(exit_code.span()=> Some((#exit_code)(err.kind))),
+            None => quote!(None),
+        };
+        let error_format = attrs
+            .error_format()
+            .cloned()
+            .unwrap_or_else(|| quote!(::structopt::ErrorFormat::Text));
+        Some(quote! {
+            fn exit_on_error(err: &::structopt::clap::Error) -> ! {
+                let code = #code;
+                ::structopt::__private::exit_with_error(err, #error_format, code)
+            }
+        })
+    } else {
+        None
+    };
+
+    let dotenv_path = attrs.dotenv().map(|path| {
+        quote! {
+            fn dotenv_path() -> ::std::option::Option<&'static str> {
+                Some(#path)
+            }
+        }
+    });
+
+    quote! {
+        #prepare_args
+        #exit_on_error
+        #dotenv_path
+    }
+}
+
+fn gen_from_default_subcommand(
+    name: &Ident,
+    variants: &Punctuated<Variant, Comma>,
+    parent_attribute: &Attrs,
+) -> TokenStream {
+    use syn::Fields::*;
+
+    let matches = format_ident!("matches");
+
+    let (has_default, constructor) = match find_default_subcommand(variants, parent_attribute) {
+        Some((variant, attrs)) => {
+            let variant_name = &variant.ident;
+            let constructor_block = match variant.fields {
+                Named(ref fields) => gen_constructor(&fields.named, &attrs),
+                Unit => quote!(),
+                Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+                    let ty = &fields.unnamed[0];
+                    quote!( ( <#ty as ::structopt::StructOpt>::from_clap(#matches) ) )
+                }
+                Unnamed(ref fields) => gen_constructor(&fields.unnamed, &attrs),
+            };
+            (
+                true,
+                quote!(Some(#name :: #variant_name #constructor_block)),
+            )
+        }
+        None => (false, quote!(None)),
+    };
+
+    quote! {
+        fn has_default_subcommand() -> bool {
+            #has_default
+        }
+
+        fn from_default_subcommand(
+            #matches: &::structopt::clap::ArgMatches
+        ) -> Option<Self> {
+            #constructor
+        }
+    }
+}
+
+/// Generate the pattern matching `variant` and the statements updating its
+/// fields from `matches`.
+fn gen_variant_updater(name: &Ident, variant: &Variant, attrs: &Attrs) -> TokenStream {
+    use syn::Fields::*;
+
+    let matches = format_ident!("matches");
+    let variant_name = &variant.ident;
+    let (pattern, updates) = match variant.fields {
+        Named(ref fields) => {
+            let (bindings, updates) = gen_updater(&fields.named, attrs, true);
+            (quote!({ #bindings .. }), updates)
+        }
+        Unit => (quote!(), quote!()),
+        Unnamed(ref fields) if fields.unnamed.len() == 1 => (
+            quote!((inner)),
+            quote!(::structopt::StructOpt::update_from_clap(inner, #matches);),
+        ),
+        Unnamed(ref fields) => {
+            let (bindings, updates) = gen_updater(&fields.unnamed, attrs, true);
+            (quote!({ #bindings .. }), updates)
+        }
+    };
+
+    quote! {
+        if let #name :: #variant_name #pattern = self {
+            #updates
+            return true;
+        }
+    }
+}
+
+fn gen_update_from_subcommand(
+    name: &Ident,
+    variants: &Punctuated<Variant, Comma>,
+    parent_attribute: &Attrs,
+) -> TokenStream {
+    use syn::Fields::*;
+
+    let matches = format_ident!("matches");
+
+    let default_update =
+        find_default_subcommand(variants, parent_attribute).map(|(variant, attrs)| {
+            let update = gen_variant_updater(name, variant, &attrs);
+            quote! {
+                #[allow(irrefutable_let_patterns)]
+                fn update_from_default_subcommand(
+                    &mut self,
+                    #matches: &::structopt::clap::ArgMatches
+                ) -> bool {
+                    #update
+                    false
+                }
+            }
+        });
 
-            _ => {
-                let app_var = Ident::new("subcommand", Span::call_site());
-                let from_attrs = attrs.top_level_methods();
-                let version = attrs.version();
+    let (flatten_variants, variants): (Vec<_>, Vec<_>) = variants
+        .iter()
+        .filter_map(|variant| {
+            let attrs = Attrs::from_struct(
+                variant.span(),
+                &variant.attrs,
+                Name::Derived(variant.ident.clone()),
+                Some(parent_attribute),
+                parent_attribute.casing(),
+                parent_attribute.env_casing(),
+                true,
+            );
+            match *attrs.kind() {
+                Kind::ExternalSubcommand | Kind::Skip(_) => None,
+                _ => Some((variant, attrs)),
+            }
+        })
+        .partition(|(_, attrs)| match &*attrs.kind() {
+            Kind::Flatten => true,
+            _ => false,
+        });
 
-                let arg_block = match variant.fields {
-                    // If the variant is named, then gen_augmentation already generates the
-                    // top level methods (#from_attrs) and version.
-                    Named(ref fields) => gen_augmentation(&fields.named, &app_var, &attrs),
-                    Unit => quote!( #app_var#from_attrs#version ),
-                    Unnamed(FieldsUnnamed { ref unnamed, .. }) if unnamed.len() == 1 => {
-                        let ty = &unnamed[0];
-                        quote_spanned! { ty.span()=>
-                            {
-                                let #app_var = <#ty as ::structopt::StructOptInternal>::augment_clap(
-                                    #app_var
-                                );
-                                if <#ty as ::structopt::StructOptInternal>::is_subcommand() {
-                                    #app_var.setting(
-                                        ::structopt::clap::AppSettings::SubcommandRequiredElseHelp
-                                    )
-                                } else {
-                                    #app_var
-                                }#from_attrs#version
-                            }
+    let match_arms = variants.iter().map(|(variant, attrs)| {
+        let sub_name = attrs.cased_name();
+        let update = gen_variant_updater(name, variant, attrs);
+        quote! {
+            (#sub_name, Some(#matches)) => { #update }
+        }
+    });
+
+    let child_subcommands = flatten_variants.iter().map(|(variant, _)| {
+        let variant_name = &variant.ident;
+        match variant.fields {
+            Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed[0];
+                quote! {
+                    if let #name :: #variant_name (inner) = self {
+                        if <#ty as ::structopt::StructOptInternal>::update_from_subcommand(inner, sub) {
+                            return true;
                         }
                     }
-                    Unnamed(..) => abort!(variant, "non single-typed tuple enums are not supported"),
-                };
-
-                let name = attrs.cased_name();
-                Some(quote! {
-                    let app = app.subcommand({
-                        let #app_var = ::structopt::clap::SubCommand::with_name(#name);
-                        #arg_block
-                    });
-                })
-            },
+                }
+            }
+            _ => abort!(
+                variant,
+                "`flatten` is usable only with single-typed tuple variants"
+            ),
         }
     });
 
-    let app_methods = parent_attribute.top_level_methods();
-    let version = parent_attribute.version();
     quote! {
-        fn augment_clap<'a, 'b>(
-            app: ::structopt::clap::App<'a, 'b>
-        ) -> ::structopt::clap::App<'a, 'b> {
-            let app = app #app_methods;
-            #( #subcommands )*;
-            app #version
+        #[allow(irrefutable_let_patterns)]
+        fn update_from_subcommand<'a, 'b>(
+            &mut self,
+            sub: (&'b str, Option<&'b ::structopt::clap::ArgMatches<'a>>)
+        ) -> bool {
+            match sub {
+                #( #match_arms, )*
+                _ => {}
+            }
+            #( #child_subcommands )*
+            match <Self as ::structopt::StructOptInternal>::from_subcommand(sub) {
+                Some(new) => {
+                    *self = new;
+                    true
+                }
+                None => false,
+            }
         }
+
+        #default_update
     }
 }
 
 fn gen_from_clap_enum() -> TokenStream {
     quote! {
+        fn update_from_clap(&mut self, matches: &::structopt::clap::ArgMatches) {
+            if !<Self as ::structopt::StructOptInternal>::update_from_subcommand(
+                self,
+                matches.subcommand(),
+            ) {
+                <Self as ::structopt::StructOptInternal>::update_from_default_subcommand(
+                    self,
+                    matches,
+                );
+            }
+        }
+
         fn from_clap(matches: &::structopt::clap::ArgMatches) -> Self {
             <Self as ::structopt::StructOptInternal>::from_subcommand(matches.subcommand())
+                .or_else(|| <Self as ::structopt::StructOptInternal>::from_default_subcommand(matches))
                 .expect("structopt misuse: You likely tried to #[flatten] a struct \
                          that contains #[subcommand]. This is forbidden.")
         }
@@ -703,10 +1834,7 @@ fn gen_from_subcommand(
                 let ty = &fields.unnamed[0];
                 quote!( ( <#ty as ::structopt::StructOpt>::from_clap(#matches) ) )
             }
-            Unnamed(..) => abort!(
-                variant.ident,
-                "non single-typed tuple enums are not supported"
-            ),
+            Unnamed(ref fields) => gen_constructor(&fields.unnamed, &attrs),
         };
 
         quote! {
@@ -877,9 +2005,20 @@ fn impl_structopt_for_struct(
     let (impl_generics, ty_generics, where_clause) = split_structopt_generics_for_impl(&generics);
 
     let basic_clap_app_gen = gen_clap_struct(attrs);
+    if let Some(multicall) = basic_clap_app_gen.attrs.multicall() {
+        abort!(multicall, "`multicall` is only allowed on top of an enum");
+    }
+    if let Some((run, _)) = basic_clap_app_gen.attrs.run() {
+        abort!(run, "`run(...)` is only allowed on top of an enum";
+            help = "implement `structopt::StructOptRun` for the struct instead");
+    }
     let augment_clap = gen_augment_clap(fields, &basic_clap_app_gen.attrs);
     let from_clap = gen_from_clap(name, fields, &basic_clap_app_gen.attrs);
+    let update_from_clap = gen_update_from_clap(fields, &basic_clap_app_gen.attrs);
+    let validate = gen_validate(fields, &basic_clap_app_gen.attrs);
+    let check_matches = gen_check_matches(fields, &basic_clap_app_gen.attrs);
     let paw_impl = gen_paw_impl(&impl_generics, name, &ty_generics, &where_clause);
+    let from_args = gen_from_args(&basic_clap_app_gen.attrs);
 
     let clap_tokens = basic_clap_app_gen.tokens;
     quote! {
@@ -900,6 +2039,10 @@ fn impl_structopt_for_struct(
         impl #impl_generics ::structopt::StructOpt for #name #ty_generics #where_clause {
             #clap_tokens
             #from_clap
+            #update_from_clap
+            #validate
+            #check_matches
+            #from_args
         }
 
         #[allow(unused_variables)]
@@ -925,6 +2068,292 @@ fn impl_structopt_for_struct(
     }
 }
 
+/// Generate the `StructOptRun` impl dispatching each variant to its handler.
+fn gen_run_impl(
+    name: &Ident,
+    variants: &Punctuated<Variant, Comma>,
+    parent_attribute: &Attrs,
+    impl_generics: &ImplGenerics,
+    ty_generics: &TypeGenerics,
+    where_clause: &TokenStream,
+) -> TokenStream {
+    use syn::Fields::*;
+
+    let spec = match parent_attribute.run() {
+        Some((_, spec)) => spec,
+        None => return quote!(),
+    };
+    let context = match &spec.context {
+        Some(ty) => quote!(#ty),
+        None => quote!(()),
+    };
+    let output = match &spec.output {
+        Some(ty) => quote!(#ty),
+        None => quote!(()),
+    };
+    let ctx = format_ident!("__structopt_ctx");
+
+    let arms = variants.iter().map(|variant| {
+        let attrs = Attrs::from_struct(
+            variant.span(),
+            &variant.attrs,
+            Name::Derived(variant.ident.clone()),
+            Some(parent_attribute),
+            parent_attribute.casing(),
+            parent_attribute.env_casing(),
+            true,
+        );
+        let variant_name = &variant.ident;
+
+        match attrs.run_handler() {
+            Some(handler) => {
+                let bindings: Vec<_> = variant
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(index, field)| match &field.ident {
+                        Some(ident) => ident.clone(),
+                        None => format_ident!("arg{}", index),
+                    })
+                    .collect();
+                let pattern = match variant.fields {
+                    Named(_) => quote!({ #( #bindings ),* }),
+                    Unnamed(_) => quote!(( #( #bindings ),* )),
+                    Unit => quote!(),
+                };
+                quote_spanned! { handler.span()=>
+                    #name::#variant_name #pattern => (#handler)(#( #bindings, )* #ctx)
+                }
+            }
+
+            None => match variant.fields {
+                Unnamed(ref fields) if fields.unnamed.len() == 1 => {
+                    let ty = &fields.unnamed[0].ty;
+                    quote_spanned! { ty.span()=>
+                        #name::#variant_name(arg) => {
+                            <#ty as ::structopt::StructOptRun<#context>>::run(arg, #ctx)
+                        }
+                    }
+                }
+                _ => abort!(
+                    variant,
+                    "variant `{}` has no `run` handler", variant_name;
+                    help = "add `#[structopt(run = path::to::handler)]` on the variant, \
+                        or use a single-typed tuple variant whose type implements `StructOptRun`"
+                ),
+            },
+        }
+    });
+
+    quote! {
+        #[allow(unknown_lints)]
+        #[allow(unused_variables, dead_code, unreachable_code)]
+        #[allow(
+            clippy::style,
+            clippy::complexity,
+            clippy::pedantic,
+            clippy::restriction,
+            clippy::perf,
+            clippy::deprecated,
+            clippy::nursery,
+            clippy::cargo
+        )]
+        #[deny(clippy::correctness)]
+        impl #impl_generics ::structopt::StructOptRun<#context> for #name #ty_generics #where_clause {
+            type Output = #output;
+
+            fn run(self, #ctx: &#context) -> #output {
+                match self {
+                    #( #arms, )*
+                }
+            }
+        }
+    }
+}
+
+/// Whether `field` is marked with `#[structopt(flatten)]`, which makes a tuple
+/// struct with this single field a newtype.
+fn is_flatten(field: &Field) -> bool {
+    parse_structopt_attributes(&field.attrs)
+        .iter()
+        .any(|attr| matches!(attr, StructOptAttr::Flatten(_)))
+}
+
+/// A newtype struct delegates everything to the wrapped type.
+fn impl_structopt_for_newtype(
+    name: &Ident,
+    field: &Field,
+    attrs: &[Attribute],
+    generics: &Generics,
+) -> TokenStream {
+    if let Some(attr) = attrs.iter().find(|attr| attr.path.is_ident("structopt")) {
+        abort!(
+            attr,
+            "structopt attributes are not allowed on newtype structs";
+            note = "newtype structs delegate to the `StructOpt` implementation of the wrapped type"
+        );
+    }
+    let field_attrs = parse_structopt_attributes(&field.attrs);
+    if field_attrs.len() > 1 {
+        let attr = field
+            .attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("structopt"));
+        abort!(
+            attr,
+            "only `flatten` is allowed on the field of a newtype struct";
+            note = "newtype structs delegate to the `StructOpt` implementation of the wrapped type"
+        );
+    }
+
+    let (impl_generics, ty_generics, where_clause) = split_structopt_generics_for_impl(&generics);
+    let ty = &field.ty;
+    let paw_impl = gen_paw_impl(&impl_generics, name, &ty_generics, &where_clause);
+
+    quote! {
+        #[allow(unknown_lints)]
+        #[allow(unused_variables, dead_code, unreachable_code)]
+        #[allow(
+            clippy::style,
+            clippy::complexity,
+            clippy::pedantic,
+            clippy::restriction,
+            clippy::perf,
+            clippy::deprecated,
+            clippy::nursery,
+            clippy::cargo
+        )]
+        #[deny(clippy::correctness)]
+        impl #impl_generics ::structopt::StructOpt for #name #ty_generics #where_clause {
+            fn clap<'a, 'b>() -> ::structopt::clap::App<'a, 'b> {
+                <#ty as ::structopt::StructOpt>::clap()
+            }
+
+            fn from_clap(matches: &::structopt::clap::ArgMatches) -> Self {
+                #name(<#ty as ::structopt::StructOpt>::from_clap(matches))
+            }
+
+            fn update_from_clap(&mut self, matches: &::structopt::clap::ArgMatches) {
+                <#ty as ::structopt::StructOpt>::update_from_clap(&mut self.0, matches)
+            }
+
+            fn validate(&self) -> ::std::result::Result<(), ::structopt::clap::Error> {
+                <#ty as ::structopt::StructOpt>::validate(&self.0)
+            }
+
+            fn check_matches(
+                matches: &::structopt::clap::ArgMatches
+            ) -> ::std::result::Result<(), ::structopt::clap::Error> {
+                <#ty as ::structopt::StructOpt>::check_matches(matches)
+            }
+
+            fn exit_on_error(err: &::structopt::clap::Error) -> ! {
+                <#ty as ::structopt::StructOpt>::exit_on_error(err)
+            }
+
+            fn prepare_args(
+                args: ::std::vec::Vec<::std::ffi::OsString>
+            ) -> ::std::vec::Vec<::std::ffi::OsString> {
+                <#ty as ::structopt::StructOpt>::prepare_args(args)
+            }
+
+            fn dotenv_path() -> ::std::option::Option<&'static str> {
+                <#ty as ::structopt::StructOpt>::dotenv_path()
+            }
+
+            fn from_args() -> Self {
+                #name(<#ty as ::structopt::StructOpt>::from_args())
+            }
+
+            fn from_args_safe() -> ::std::result::Result<Self, ::structopt::clap::Error> {
+                <#ty as ::structopt::StructOpt>::from_args_safe().map(#name)
+            }
+
+            fn from_iter<I>(iter: I) -> Self
+            where
+                Self: Sized,
+                I: ::std::iter::IntoIterator,
+                I::Item: ::std::convert::Into<::std::ffi::OsString> + Clone,
+            {
+                #name(<#ty as ::structopt::StructOpt>::from_iter(iter))
+            }
+
+            fn from_iter_safe<I>(iter: I) -> ::std::result::Result<Self, ::structopt::clap::Error>
+            where
+                Self: Sized,
+                I: ::std::iter::IntoIterator,
+                I::Item: ::std::convert::Into<::std::ffi::OsString> + Clone,
+            {
+                <#ty as ::structopt::StructOpt>::from_iter_safe(iter).map(#name)
+            }
+        }
+
+        #[allow(unknown_lints)]
+        #[allow(unused_variables, dead_code, unreachable_code)]
+        #[allow(
+            clippy::style,
+            clippy::complexity,
+            clippy::pedantic,
+            clippy::restriction,
+            clippy::perf,
+            clippy::deprecated,
+            clippy::nursery,
+            clippy::cargo
+        )]
+        #[deny(clippy::correctness)]
+        impl #impl_generics ::structopt::StructOptInternal for #name #ty_generics #where_clause {
+            fn augment_clap<'a, 'b>(
+                app: ::structopt::clap::App<'a, 'b>
+            ) -> ::structopt::clap::App<'a, 'b> {
+                <#ty as ::structopt::StructOptInternal>::augment_clap(app)
+            }
+
+            fn is_subcommand() -> bool {
+                <#ty as ::structopt::StructOptInternal>::is_subcommand()
+            }
+
+            fn from_subcommand<'a, 'b>(
+                sub: (&'b str, Option<&'b ::structopt::clap::ArgMatches<'a>>)
+            ) -> Option<Self> {
+                <#ty as ::structopt::StructOptInternal>::from_subcommand(sub).map(#name)
+            }
+
+            fn update_from_subcommand<'a, 'b>(
+                &mut self,
+                sub: (&'b str, Option<&'b ::structopt::clap::ArgMatches<'a>>)
+            ) -> bool {
+                <#ty as ::structopt::StructOptInternal>::update_from_subcommand(&mut self.0, sub)
+            }
+
+            fn has_subcommand(name: &str) -> bool {
+                <#ty as ::structopt::StructOptInternal>::has_subcommand(name)
+            }
+
+            fn has_default_subcommand() -> bool {
+                <#ty as ::structopt::StructOptInternal>::has_default_subcommand()
+            }
+
+            fn from_default_subcommand(
+                matches: &::structopt::clap::ArgMatches
+            ) -> Option<Self> {
+                <#ty as ::structopt::StructOptInternal>::from_default_subcommand(matches).map(#name)
+            }
+
+            fn update_from_default_subcommand(
+                &mut self,
+                matches: &::structopt::clap::ArgMatches
+            ) -> bool {
+                <#ty as ::structopt::StructOptInternal>::update_from_default_subcommand(
+                    &mut self.0,
+                    matches,
+                )
+            }
+        }
+
+        #paw_impl
+    }
+}
+
 fn impl_structopt_for_enum(
     name: &Ident,
     variants: &Punctuated<Variant, Comma>,
@@ -939,8 +2368,22 @@ fn impl_structopt_for_enum(
 
     let augment_clap = gen_augment_clap_enum(variants, &attrs);
     let from_clap = gen_from_clap_enum();
+    let validate = gen_validate_enum(name, variants, &attrs);
+    let check_matches = gen_check_matches_enum(variants, &attrs);
     let from_subcommand = gen_from_subcommand(name, variants, &attrs);
+    let update_from_subcommand = gen_update_from_subcommand(name, variants, &attrs);
+    let from_default_subcommand = gen_from_default_subcommand(name, variants, &attrs);
+    let has_subcommand = gen_has_subcommand(variants, &attrs);
+    let from_args = gen_from_args(&attrs);
     let paw_impl = gen_paw_impl(&impl_generics, name, &ty_generics, &where_clause);
+    let run_impl = gen_run_impl(
+        name,
+        variants,
+        &attrs,
+        &impl_generics,
+        &ty_generics,
+        &where_clause,
+    );
 
     quote! {
         #[allow(unknown_lints)]
@@ -959,6 +2402,9 @@ fn impl_structopt_for_enum(
         impl #impl_generics ::structopt::StructOpt for #name #ty_generics #where_clause {
             #clap_tokens
             #from_clap
+            #validate
+            #check_matches
+            #from_args
         }
 
         #[allow(unused_variables)]
@@ -978,10 +2424,15 @@ fn impl_structopt_for_enum(
         impl #impl_generics ::structopt::StructOptInternal for #name #ty_generics #where_clause {
             #augment_clap
             #from_subcommand
+            #update_from_subcommand
+            #from_default_subcommand
+            #has_subcommand
             fn is_subcommand() -> bool { true }
         }
 
         #paw_impl
+
+        #run_impl
     }
 }
 
@@ -1008,9 +2459,26 @@ fn impl_structopt(input: &DeriveInput) -> TokenStream {
             fields: syn::Fields::Named(ref fields),
             ..
         }) => impl_structopt_for_struct(struct_name, &fields.named, &input.attrs, &input.generics),
+        Struct(DataStruct {
+            fields: syn::Fields::Unnamed(ref fields),
+            ..
+        }) if fields.unnamed.len() == 1 && is_flatten(&fields.unnamed[0]) => {
+            impl_structopt_for_newtype(
+                struct_name,
+                &fields.unnamed[0],
+                &input.attrs,
+                &input.generics,
+            )
+        }
+        Struct(DataStruct {
+            fields: syn::Fields::Unnamed(ref fields),
+            ..
+        }) => {
+            impl_structopt_for_struct(struct_name, &fields.unnamed, &input.attrs, &input.generics)
+        }
         Enum(ref e) => {
             impl_structopt_for_enum(struct_name, &e.variants, &input.attrs, &input.generics)
         }
-        _ => abort_call_site!("structopt only supports non-tuple structs and enums"),
+        _ => abort_call_site!("structopt only supports structs with fields and enums"),
     }
 }
@@ -0,0 +1,73 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! `#[structopt::main]` attribute handling
+
+use proc_macro2::TokenStream;
+use proc_macro_error::{abort, set_dummy};
+use quote::{format_ident, quote, quote_spanned};
+use syn::{spanned::Spanned, FnArg, ItemFn, PatType};
+
+/// Wraps `fn main(opt: Opt) -> R` into a `fn main()` parsing `Opt` from the
+/// command line and reporting `R`.
+pub fn impl_main(args: TokenStream, item: ItemFn) -> TokenStream {
+    // keep the function around on error to avoid cascading errors
+    set_dummy(quote!(#item));
+
+    if !args.is_empty() {
+        abort!(args, "`#[structopt::main]` does not take arguments");
+    }
+
+    let sig = &item.sig;
+    if let Some(asyncness) = &sig.asyncness {
+        abort!(
+            asyncness,
+            "`#[structopt::main]` does not support async functions"
+        );
+    }
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        abort!(
+            sig.generics,
+            "`#[structopt::main]` does not support generic functions"
+        );
+    }
+
+    let ty = match (sig.inputs.len(), sig.inputs.first()) {
+        (1, Some(FnArg::Typed(PatType { ty, .. }))) => ty,
+        _ => abort!(
+            sig.paren_token.span,
+            "`#[structopt::main]` function must take exactly one argument";
+            help = "use `fn main(opt: Opt)` where `Opt` implements `StructOpt`"
+        ),
+    };
+
+    let attrs = &item.attrs;
+    let vis = &item.vis;
+    let name = &sig.ident;
+    let inner = format_ident!("__structopt_{}", name);
+    let inputs = &sig.inputs;
+    let output = &sig.output;
+    let block = &item.block;
+
+    let call = quote_spanned! { output.span()=>
+        ::structopt::__private::MainResult::report(#inner(opt))
+    };
+
+    quote! {
+        #( #attrs )*
+        #vis fn #name() {
+            fn #inner(#inputs) #output #block
+
+            let opt = <#ty as ::structopt::StructOpt>::from_args();
+            let code = #call;
+            if code != 0 {
+                ::std::process::exit(code);
+            }
+        }
+    }
+}
@@ -6,7 +6,7 @@ use syn::{
     self, parenthesized,
     parse::{Parse, ParseBuffer, ParseStream},
     punctuated::Punctuated,
-    Attribute, Expr, ExprLit, Ident, Lit, LitBool, LitStr, Token,
+    Attribute, Expr, ExprLit, Ident, Lit, LitBool, LitStr, Token, Type,
 };
 
 pub enum StructOptAttr {
@@ -14,9 +14,14 @@ pub enum StructOptAttr {
     Short(Ident),
     Long(Ident),
     Env(Ident),
+    EnvFile(Ident),
+    Sensitive(Ident),
     Flatten(Ident),
     Subcommand(Ident),
     ExternalSubcommand(Ident),
+    DefaultSubcommand(Ident),
+    Multicall(Ident),
+    AutoShort(Ident),
     NoVersion(Ident),
     VerbatimDocComment(Ident),
 
@@ -37,6 +42,33 @@ pub enum StructOptAttr {
     // ident [= arbitrary_expr]
     Skip(Ident, Option<Expr>),
 
+    // run(context = Type, output = Type)
+    Run(Ident, RunSpec),
+
+    // run = handler_expr
+    RunHandler(Ident, Expr),
+
+    // exit_code = mapping_expr
+    ExitCode(Ident, Expr),
+
+    // error_format = "json"
+    ErrorFormat(Ident, LitStr),
+
+    // env_prefix = "APP_"
+    EnvPrefix(Ident, LitStr),
+
+    // dotenv = "path/to/.env"
+    Dotenv(Ident, LitStr),
+
+    // validate = path::to::fn
+    Validate(Ident, Expr),
+
+    // range = 1..=65535
+    Range(Ident, Expr),
+
+    // len = 1..=64
+    Len(Ident, Expr),
+
     // ident = arbitrary_expr
     NameExpr(Ident, Expr),
 
@@ -90,6 +122,20 @@ impl Parse for StructOptAttr {
                         Ok(About(name, Some(lit)))
                     }
 
+                    "error_format" => Ok(ErrorFormat(name, lit)),
+                    "env_prefix" => Ok(EnvPrefix(name, lit)),
+                    "dotenv" => Ok(Dotenv(name, lit)),
+
+                    "run" => abort!(
+                        lit,
+                        "`run` handler must be a path or a closure, not a string literal"
+                    ),
+
+                    "validate" => abort!(
+                        lit,
+                        "`validate` must be a path or a closure, not a string literal"
+                    ),
+
                     "skip" => {
                         let expr = ExprLit {
                             attrs: vec![],
@@ -106,6 +152,16 @@ impl Parse for StructOptAttr {
                     Ok(expr) => {
                         if name_str == "skip" {
                             Ok(Skip(name, Some(expr)))
+                        } else if name_str == "run" {
+                            Ok(RunHandler(name, expr))
+                        } else if name_str == "exit_code" {
+                            Ok(ExitCode(name, expr))
+                        } else if name_str == "validate" {
+                            Ok(Validate(name, expr))
+                        } else if name_str == "range" {
+                            Ok(Range(name, expr))
+                        } else if name_str == "len" {
+                            Ok(Len(name, expr))
                         } else {
                             Ok(NameExpr(name, expr))
                         }
@@ -127,13 +183,26 @@ impl Parse for StructOptAttr {
                     let parser_specs: Punctuated<ParserSpec, Token![,]> =
                         nested.parse_terminated(ParserSpec::parse)?;
 
-                    if parser_specs.len() == 1 {
-                        Ok(Parse(name, parser_specs[0].clone()))
-                    } else {
-                        abort!(name, "`parse` must have exactly one argument")
+                    match parser_specs.len() {
+                        1 => Ok(Parse(name, parser_specs[0].clone())),
+
+                        // `parse(kind = parser, context)`
+                        2 if parser_specs[1].kind == "context"
+                            && parser_specs[1].eq_token.is_none() =>
+                        {
+                            let mut spec = parser_specs[0].clone();
+                            spec.context = Some(parser_specs[1].kind.clone());
+                            Ok(Parse(name, spec))
+                        }
+
+                        _ => abort!(name, "`parse` must have exactly one argument";
+                            help = "the only other argument allowed is `context`, \
+                                as in `parse(try_from_str = parser, context)`"),
                     }
                 }
 
+                "run" => Ok(Run(name, nested.parse()?)),
+
                 "raw" => match nested.parse::<LitBool>() {
                     Ok(bool_token) => {
                         let expr = ExprLit {
@@ -167,9 +236,15 @@ impl Parse for StructOptAttr {
                 "long" => Ok(Long(name)),
                 "short" => Ok(Short(name)),
                 "env" => Ok(Env(name)),
+                "env_file" => Ok(EnvFile(name)),
+                "sensitive" => Ok(Sensitive(name)),
                 "flatten" => Ok(Flatten(name)),
                 "subcommand" => Ok(Subcommand(name)),
                 "external_subcommand" => Ok(ExternalSubcommand(name)),
+                "default_subcommand" => Ok(DefaultSubcommand(name)),
+                "multicall" => Ok(Multicall(name)),
+                "auto_short" => Ok(AutoShort(name)),
+                "run" => Ok(Run(name, RunSpec::default())),
                 "no_version" => Ok(NoVersion(name)),
                 "verbatim_doc_comment" => Ok(VerbatimDocComment(name)),
 
@@ -197,6 +272,7 @@ pub struct ParserSpec {
     pub kind: Ident,
     pub eq_token: Option<Token![=]>,
     pub parse_func: Option<Expr>,
+    pub context: Option<Ident>,
 }
 
 impl Parse for ParserSpec {
@@ -213,10 +289,45 @@ impl Parse for ParserSpec {
             kind,
             eq_token,
             parse_func,
+            context: None,
         })
     }
 }
 
+#[derive(Clone, Default)]
+pub struct RunSpec {
+    pub context: Option<Type>,
+    pub output: Option<Type>,
+}
+
+impl Parse for RunSpec {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let mut spec = RunSpec::default();
+
+        while !input.is_empty() {
+            let name: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let ty: Type = input.parse()?;
+
+            match &*name.to_string() {
+                "context" => spec.context = Some(ty),
+                "output" => spec.output = Some(ty),
+                s => abort!(
+                    name,
+                    "unexpected `run` argument: {}", s;
+                    help = "expected `context = Type` and/or `output = Type`"
+                ),
+            }
+
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(spec)
+    }
+}
+
 fn raw_method_suggestion(ts: ParseBuffer) -> String {
     let do_parse = move || -> Result<(Ident, Punctuated<Expr, Token![,]>), syn::Error> {
         let name = ts.parse()?;
@@ -40,6 +40,65 @@ impl Ty {
     }
 }
 
+/// The type of the values of a field of type `field_ty`, given by its parser.
+pub fn convert_type(ty: Ty, field_ty: &syn::Type) -> &syn::Type {
+    match ty {
+        Ty::Vec | Ty::Option => sub_type(field_ty).unwrap_or(field_ty),
+        Ty::OptionOption | Ty::OptionVec => {
+            sub_type(field_ty).and_then(sub_type).unwrap_or(field_ty)
+        }
+        _ => field_ty,
+    }
+}
+
+/// Whether `ty` is `PathBuf` or `OsString`, also named with a path like
+/// `std::path::PathBuf`, which are parsed from `OsStr` by default.
+pub fn is_os_str_ty(ty: &syn::Type) -> bool {
+    is_std_ty(ty, &["std"], "path", "PathBuf") || is_std_ty(ty, &["std"], "ffi", "OsString")
+}
+
+/// Whether `ty` is `Duration`, also named with a path like
+/// `std::time::Duration`, which is parsed by `structopt::parsers::duration`
+/// by default.
+pub fn is_duration_ty(ty: &syn::Type) -> bool {
+    is_std_ty(ty, &["std", "core"], "time", "Duration")
+}
+
+/// Whether `ty` is the type `name` of the module `module` of one of the
+/// `crates`, named either `name`, `module::name` or `krate::module::name`.
+fn is_std_ty(ty: &syn::Type, crates: &[&str], module: &str, name: &str) -> bool {
+    let path = match strip_group(ty) {
+        Type::Path(TypePath { qself: None, path }) => path,
+        _ => return false,
+    };
+    if path.segments.iter().any(|seg| !seg.arguments.is_empty()) {
+        return false;
+    }
+    let segments: Vec<_> = path.segments.iter().map(|seg| &seg.ident).collect();
+    match &*segments {
+        [ty] => path.leading_colon.is_none() && *ty == name,
+        [m, ty] => path.leading_colon.is_none() && *m == module && *ty == name,
+        [krate, m, ty] => crates.iter().any(|c| *krate == c) && *m == module && *ty == name,
+        _ => false,
+    }
+}
+
+/// Whether the values of `ty` are `Secret<T>`, possibly in an `Option` or a
+/// `Vec`, also named with a path like `structopt::Secret<T>`.
+pub fn is_secret_ty(ty: &syn::Type) -> bool {
+    let ty = strip_group(ty);
+    if let Some(subty) = subty_if(ty, |seg| seg.ident == "Option" || seg.ident == "Vec") {
+        return is_secret_ty(subty);
+    }
+
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path.segments.last().map_or(false, |seg| {
+            seg.ident == "Secret" && matches!(seg.arguments, AngleBracketed(_))
+        }),
+        _ => false,
+    }
+}
+
 pub fn sub_type(ty: &syn::Type) -> Option<&syn::Type> {
     subty_if(ty, |_| true)
 }
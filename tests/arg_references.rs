@@ -0,0 +1,99 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::clap::ErrorKind;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(rename_all = "screaming-snake")]
+struct Opt {
+    #[structopt(long, conflicts_with = output_dir)]
+    output_file: Option<String>,
+    #[structopt(long, requires = format)]
+    output_dir: Option<String>,
+    #[structopt(long, name = "fmt", required_unless_one = [output_file, output_dir])]
+    format: Option<String>,
+    #[structopt(long, conflicts_with_all = &[output_file, output_dir, format])]
+    stdout: bool,
+    #[structopt(long, conflicts_with = "STDOUT")]
+    quiet: bool,
+}
+
+fn parse(args: &[&str]) -> Result<Opt, structopt::clap::Error> {
+    Opt::from_iter_safe(std::iter::once("test").chain(args.iter().cloned()))
+}
+
+#[test]
+fn conflicts_with_field() {
+    let err = parse(&["--OUTPUT_FILE", "a", "--OUTPUT_DIR", "b", "--FORMAT", "x"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn requires_field() {
+    let err = parse(&["--OUTPUT_DIR", "b"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MissingRequiredArgument);
+    assert!(err.message.contains("--FORMAT"));
+
+    let opt = parse(&["--OUTPUT_DIR", "b", "--FORMAT", "x"]).unwrap();
+    assert_eq!(opt.output_dir, Some("b".to_string()));
+    assert_eq!(opt.format, Some("x".to_string()));
+}
+
+#[test]
+fn required_unless_fields() {
+    let err = parse(&[]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MissingRequiredArgument);
+
+    assert!(parse(&["--OUTPUT_FILE", "a"]).is_ok());
+}
+
+#[test]
+fn conflicts_with_all_fields() {
+    let err = parse(&["--STDOUT", "--OUTPUT_FILE", "a"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn string_names_still_work() {
+    let err = parse(&["--STDOUT", "--QUIET", "--FORMAT", "x"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn references_use_arg_names() {
+    let err = parse(&["--OUTPUT_DIR", "b"]).unwrap_err();
+    assert!(err.message.contains("--FORMAT <fmt>"));
+}
+
+#[test]
+fn constants_are_not_fields() {
+    const OUT: &str = "output";
+    const NAMES: &[&str] = &["output", "verbose"];
+
+    #[derive(StructOpt, Debug)]
+    struct Opt {
+        #[structopt(long, conflicts_with = OUT)]
+        input: Option<String>,
+        #[structopt(long)]
+        output: Option<String>,
+        #[structopt(long)]
+        verbose: bool,
+        #[structopt(long, conflicts_with_all = NAMES)]
+        quiet: bool,
+        #[structopt(long, requires_all = &[OUT, input])]
+        force: bool,
+    }
+
+    let err = Opt::from_iter_safe(&["test", "--input", "a", "--output", "b"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ArgumentConflict);
+    let err = Opt::from_iter_safe(&["test", "--quiet", "--verbose"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ArgumentConflict);
+    let err = Opt::from_iter_safe(&["test", "--force", "--output", "b"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::MissingRequiredArgument);
+}
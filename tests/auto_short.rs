@@ -0,0 +1,85 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use structopt::clap::ErrorKind;
+use structopt::StructOpt;
+use utils::*;
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(auto_short)]
+struct Opt {
+    #[structopt(long)]
+    verbose: bool,
+    #[structopt(long)]
+    version_file: Option<String>,
+    #[structopt(long)]
+    host: Option<String>,
+    #[structopt(short = "o", long)]
+    output: Option<String>,
+    #[structopt(long)]
+    out: Option<String>,
+    #[structopt(long = "use")]
+    usage: bool,
+    #[structopt(long)]
+    vv: bool,
+    input: Option<String>,
+    #[structopt(subcommand)]
+    cmd: Option<Command>,
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(auto_short)]
+enum Command {
+    Run {
+        #[structopt(long)]
+        jobs: Option<u32>,
+    },
+}
+
+#[test]
+fn first_unused_letter() {
+    let opt = Opt::from_iter(&["test", "-v", "-e", "file", "-o", "x", "-u", "y"]);
+    assert!(opt.verbose);
+    assert_eq!(opt.version_file, Some("file".to_string()));
+    assert_eq!(opt.output, Some("x".to_string()));
+    assert_eq!(opt.out, Some("y".to_string()));
+}
+
+#[test]
+fn builtin_flags_are_reserved() {
+    let opt = Opt::from_iter(&["test", "-s", "localhost"]);
+    assert_eq!(opt.host, Some("localhost".to_string()));
+    let err = Opt::from_iter_safe(&["test", "-h"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::HelpDisplayed);
+    let err = Opt::from_iter_safe(&["test", "-V"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::VersionDisplayed);
+}
+
+#[test]
+fn other_case() {
+    let opt = Opt::from_iter(&["test", "-U"]);
+    assert!(opt.usage);
+}
+
+#[test]
+fn no_letter_left() {
+    assert!(get_help::<Opt>().contains("        --vv"));
+}
+
+#[test]
+fn enum_variants() {
+    let opt = Opt::from_iter(&["test", "run", "-j", "4"]);
+    assert_eq!(opt.cmd, Some(Command::Run { jobs: Some(4) }));
+}
+
+#[test]
+fn unique_flags() {
+    Opt::debug_assert();
+}
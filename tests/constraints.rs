@@ -0,0 +1,148 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use std::path::PathBuf;
+
+use structopt::clap::ErrorKind;
+use structopt::StructOpt;
+use utils::*;
+
+const MAX_JOBS: u32 = 16;
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Opt {
+    /// The port to listen on
+    #[structopt(long, range = 1..=65535)]
+    port: u32,
+    #[structopt(long, range = 1..=MAX_JOBS, default_value = "4")]
+    jobs: u32,
+    #[structopt(long, range = 0.0..1.0)]
+    ratio: Option<f64>,
+    /// The name of the service
+    #[structopt(long, len = 1..=8)]
+    name: Option<String>,
+    #[structopt(long, len = ..=2, range = 10..)]
+    retries: Vec<u8>,
+    #[structopt(long, parse(from_os_str), len = 1..)]
+    out: Option<PathBuf>,
+}
+
+fn parse(args: &[&str]) -> Result<Opt, structopt::clap::Error> {
+    Opt::from_iter_safe(std::iter::once("test").chain(args.iter().cloned()))
+}
+
+#[test]
+fn in_range() {
+    assert_eq!(
+        Opt {
+            port: 80,
+            jobs: 16,
+            ratio: Some(0.5),
+            name: Some("svc".to_string()),
+            retries: vec![10, 20],
+            out: Some(PathBuf::from("a")),
+        },
+        parse(&[
+            "--port",
+            "80",
+            "--jobs",
+            "16",
+            "--ratio",
+            "0.5",
+            "--name",
+            "svc",
+            "--retries",
+            "10",
+            "--retries",
+            "20",
+            "--out",
+            "a",
+        ])
+        .unwrap()
+    );
+}
+
+#[test]
+fn out_of_range() {
+    let err = parse(&["--port", "0"]).unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+    assert!(err.message.contains("0 is not in 1..=65535"));
+
+    let err = parse(&["--port", "80", "--jobs", "17"]).unwrap_err();
+    assert!(err.message.contains("17 is not in 1..=16"));
+
+    let err = parse(&["--port", "80", "--ratio", "1"]).unwrap_err();
+    assert!(err.message.contains("1 is not in 0.0..1.0"));
+
+    let err = parse(&["--port", "80", "--retries", "9"]).unwrap_err();
+    assert!(err.message.contains("9 is not in 10.."));
+
+    // parse errors come first
+    let err = parse(&["--port", "eighty"]).unwrap_err();
+    assert!(err.message.contains("invalid digit"));
+}
+
+#[test]
+fn length() {
+    let err = parse(&["--port", "80", "--name", "too-long-name"]).unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+    assert!(err.message.contains("the length 13 is not in 1..=8"));
+
+    let err = parse(&["--port", "80", "--out", ""]).unwrap_err();
+    assert!(err.message.contains("the length 0 is not in 1.."));
+
+    // clap knows the number of values of a `Vec`
+    let err = parse(&["--port", "80", "--retries", "10", "11", "12"]).unwrap_err();
+    assert_eq!(ErrorKind::TooManyValues, err.kind);
+    let matches =
+        Opt::clap().get_matches_from_safe(&["test", "--port", "80", "--retries", "10", "11", "12"]);
+    assert_eq!(ErrorKind::TooManyValues, matches.unwrap_err().kind);
+
+    #[derive(StructOpt, Debug)]
+    struct Pair {
+        #[structopt(long, len = 2..=2)]
+        point: Vec<i32>,
+    }
+
+    let err = Pair::from_iter_safe(&["test", "--point", "1"]).unwrap_err();
+    assert_eq!(ErrorKind::TooFewValues, err.kind);
+    assert!(err.message.contains("requires at least 2 values"));
+
+    // but not when the argument is missing
+    let err = Pair::from_iter_safe(&["test"]).unwrap_err();
+    assert_eq!(ErrorKind::WrongNumberOfValues, err.kind);
+    assert!(err
+        .message
+        .contains("'point' got 0 values, which is not in 2..=2"));
+}
+
+#[test]
+fn constraints_in_help() {
+    let help = get_help::<Opt>();
+    assert!(help.contains("The port to listen on [range: 1..=65535]"));
+    assert!(help.contains("[range: 1..=16]"));
+    assert!(help.contains("The name of the service [length: 1..=8]"));
+    assert!(help.contains("[range: 10..] [length: ..=2]"));
+}
+
+#[test]
+fn sensitive_range() {
+    #[derive(StructOpt, Debug)]
+    struct Opt {
+        #[structopt(long, sensitive, range = 1000..10000)]
+        pin: u32,
+    }
+
+    let err = Opt::from_iter_safe(&["test", "--pin", "123"]).unwrap_err();
+    assert!(err
+        .message
+        .contains("the value isn't shown, as the argument is sensitive"));
+    assert!(!err.message.contains("123"));
+}
@@ -308,3 +308,76 @@ fn test_cstring() {
         .get_matches_from_safe(&["test", "bla\0bla"])
         .is_err());
 }
+
+#[derive(StructOpt, PartialEq, Debug)]
+struct DefaultOsStrOpt {
+    #[structopt(short, long)]
+    path: PathBuf,
+
+    #[structopt(short)]
+    option_path: Option<std::path::PathBuf>,
+
+    #[structopt(short)]
+    vector_os_string: Vec<OsString>,
+
+    #[structopt(short, parse(try_from_str))]
+    utf8_path: Option<PathBuf>,
+}
+
+#[test]
+fn test_default_os_str_parser() {
+    assert_eq!(
+        DefaultOsStrOpt {
+            path: PathBuf::from("/usr/bin"),
+            option_path: Some(PathBuf::from("j.zip")),
+            vector_os_string: vec![OsString::from("a"), OsString::from("b")],
+            utf8_path: Some(PathBuf::from("c")),
+        },
+        DefaultOsStrOpt::from_iter(&[
+            "test", "-p", "/usr/bin", "-o", "j.zip", "-v", "a", "-v", "b", "-u", "c"
+        ])
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_default_os_str_parser_non_utf8() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let non_utf8 = OsStr::from_bytes(b"caf\xe9");
+    let opt = DefaultOsStrOpt::from_iter(&[OsStr::new("test"), OsStr::new("-p"), non_utf8]);
+    assert_eq!(opt.path, PathBuf::from(non_utf8));
+}
+
+mod my {
+    /// A path which must be valid UTF-8, with its own `FromStr`.
+    #[derive(PartialEq, Debug)]
+    pub struct PathBuf(pub String);
+
+    impl std::str::FromStr for PathBuf {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Ok(PathBuf(s.to_owned()))
+        }
+    }
+}
+
+#[test]
+fn test_other_path_bufs_use_from_str() {
+    #[derive(StructOpt, PartialEq, Debug)]
+    struct Opt {
+        #[structopt(long)]
+        path: my::PathBuf,
+        #[structopt(long)]
+        os_string: std::ffi::OsString,
+    }
+
+    assert_eq!(
+        Opt {
+            path: my::PathBuf("a".into()),
+            os_string: OsString::from("b"),
+        },
+        Opt::from_iter(&["test", "--path", "a", "--os-string", "b"])
+    );
+}
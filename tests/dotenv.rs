@@ -0,0 +1,130 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+#![cfg(feature = "dotenv")]
+
+use std::path::PathBuf;
+
+use structopt::clap::{AppSettings, ErrorKind};
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Opt {
+    #[structopt(long, env = "DOTENV_TEST_NAME")]
+    name: String,
+    #[structopt(long, env = "DOTENV_TEST_LEVEL", default_value = "info")]
+    level: String,
+    #[structopt(long, env = "DOTENV_TEST_MISSING", default_value = "default")]
+    missing: String,
+    #[structopt(env = "DOTENV_TEST_JOBS")]
+    jobs: u32,
+}
+
+fn parse(dotenv: &str, args: &[&str]) -> Result<Opt, structopt::clap::Error> {
+    let app = structopt::dotenv::apply(Opt::clap(), dotenv)?;
+    Ok(Opt::from_clap(&app.get_matches_from_safe(args)?))
+}
+
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join("structopt-dotenv-tests");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn values_from_file() {
+    assert_eq!(
+        Opt {
+            name: "from-file".to_string(),
+            level: "warn".to_string(),
+            missing: "default".to_string(),
+            jobs: 3,
+        },
+        parse("tests/dotenv.env", &["test"]).unwrap()
+    );
+    assert!(std::env::var_os("DOTENV_TEST_NAME").is_none());
+}
+
+#[test]
+fn command_line_wins() {
+    let opt = parse("tests/dotenv.env", &["test", "--name", "cli", "7"]).unwrap();
+    assert_eq!("cli", opt.name);
+    assert_eq!(7, opt.jobs);
+}
+
+#[test]
+fn environment_wins() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    struct Opt {
+        #[structopt(long, env = "DOTENV_TEST_REAL")]
+        real: String,
+    }
+
+    let path = temp_file("real.env", "DOTENV_TEST_REAL=from-file\n");
+    std::env::set_var("DOTENV_TEST_REAL", "from-env");
+    let app = structopt::dotenv::apply(Opt::clap(), path).unwrap();
+    let opt = Opt::from_clap(&app.get_matches_from(&["test"]));
+    assert_eq!("from-env", opt.real);
+}
+
+#[test]
+fn subcommands() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    enum Cmd {
+        Run {
+            #[structopt(long, env = "DOTENV_TEST_LEVEL")]
+            level: String,
+        },
+    }
+
+    let app = structopt::dotenv::apply(Cmd::clap(), "tests/dotenv.env").unwrap();
+    assert_eq!(
+        Cmd::Run {
+            level: "warn".to_string()
+        },
+        Cmd::from_clap(&app.get_matches_from(&["test", "run"]))
+    );
+}
+
+#[test]
+fn missing_file_is_ignored() {
+    let err = parse("tests/no-such.env", &["test"]).unwrap_err();
+    assert_eq!(ErrorKind::MissingRequiredArgument, err.kind);
+}
+
+#[test]
+fn invalid_file() {
+    let path = temp_file("invalid.env", "NOT A VARIABLE\n");
+    let err = parse(path.to_str().unwrap(), &["test"]).unwrap_err();
+    assert_eq!(ErrorKind::Format, err.kind);
+    assert!(err.message.contains("invalid.env"));
+}
+
+#[test]
+fn dotenv_attribute() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    #[structopt(
+        dotenv = "tests/dotenv.env",
+        setting = AppSettings::TrailingVarArg,
+        setting = AppSettings::AllowLeadingHyphen
+    )]
+    struct Opt {
+        #[structopt(long, env = "DOTENV_TEST_NAME")]
+        name: String,
+        // the arguments given to the test harness
+        rest: Vec<String>,
+    }
+
+    assert_eq!("from-file", Opt::from_args_safe().unwrap().name);
+    assert_eq!("from-file", Opt::from_args().name);
+    // other constructors don't read the file
+    let err = Opt::from_iter_safe(&["test"]).unwrap_err();
+    assert_eq!(ErrorKind::MissingRequiredArgument, err.kind);
+}
@@ -0,0 +1,90 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug)]
+struct Opt {
+    #[structopt(short)]
+    verbose: bool,
+    #[structopt(flatten)]
+    common: Common,
+    #[structopt(subcommand)]
+    cmd: Command,
+}
+
+#[derive(StructOpt, Debug)]
+struct Common {
+    #[structopt(short, long)]
+    quiet: bool,
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    Run {
+        #[structopt(short = "h", long)]
+        host: String,
+        #[structopt(long)]
+        port: u16,
+    },
+}
+
+#[derive(StructOpt, Debug)]
+struct Clashing {
+    #[structopt(short, long)]
+    verbose: bool,
+    #[structopt(flatten)]
+    common: Verbosity,
+}
+
+#[derive(StructOpt, Debug)]
+struct Verbosity {
+    #[structopt(short = "v")]
+    verbosity: u8,
+}
+
+#[test]
+fn unique_flags() {
+    Opt::debug_assert();
+}
+
+#[test]
+fn explicit_help_short() {
+    let opt = Opt::from_iter(&["test", "run", "-h", "localhost", "--port", "80"]);
+    match opt.cmd {
+        Command::Run { host, port } => assert_eq!((host.as_str(), port), ("localhost", 80)),
+    }
+}
+
+#[test]
+#[should_panic]
+fn flattened_duplicate() {
+    Clashing::debug_assert();
+}
+
+#[test]
+fn derived_short_replaces_builtin() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    struct Opt {
+        #[structopt(short, long)]
+        host: String,
+        #[structopt(short = "V")]
+        verbose: bool,
+    }
+
+    Opt::debug_assert();
+    assert_eq!(
+        Opt {
+            host: "localhost".into(),
+            verbose: true
+        },
+        Opt::from_iter(&["test", "-h", "localhost", "-V"])
+    );
+    let err = Opt::from_iter_safe(&["test", "--help"]).unwrap_err();
+    assert_eq!(err.kind, structopt::clap::ErrorKind::HelpDisplayed);
+}
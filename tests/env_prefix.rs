@@ -0,0 +1,160 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use structopt::clap::ErrorKind;
+use structopt::StructOpt;
+use utils::*;
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(env_prefix = "ENV_PREFIX_")]
+struct Opt {
+    #[structopt(long)]
+    max_jobs: u32,
+    #[structopt(long, env)]
+    log_level: Option<String>,
+    #[structopt(long, env = "ENV_PREFIX_TEST_OTHER")]
+    other: Option<String>,
+    #[structopt(short)]
+    verbose: bool,
+}
+
+#[test]
+fn prefixed_env_in_help() {
+    let help = get_help::<Opt>();
+    assert!(help.contains("[env: ENV_PREFIX_MAX_JOBS="));
+    assert!(help.contains("[env: ENV_PREFIX_LOG_LEVEL=]"));
+    assert!(help.contains("[env: ENV_PREFIX_TEST_OTHER=]"));
+    assert!(!help.contains("ENV_PREFIX_VERBOSE"));
+}
+
+#[test]
+fn rename_all_env_and_variants() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    #[structopt(env_prefix = "tool-", rename_all_env = "kebab")]
+    enum Cmd {
+        Run {
+            #[structopt(long)]
+            dry_run: Option<String>,
+        },
+        #[structopt(env_prefix = "build_", rename_all_env = "snake")]
+        Build {
+            #[structopt(long)]
+            target_dir: Option<String>,
+        },
+    }
+
+    let help = get_subcommand_long_help::<Cmd>("run");
+    assert!(help.contains("[env: tool-dry-run=]"));
+    let help = get_subcommand_long_help::<Cmd>("build");
+    assert!(help.contains("[env: build_target_dir=]"));
+}
+
+#[test]
+fn from_env() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    #[structopt(env_prefix = "ENV_PREFIX_FROM_ENV_")]
+    struct Config {
+        #[structopt(long)]
+        port: u16,
+        #[structopt(long, default_value = "info")]
+        log_level: String,
+        files: Vec<String>,
+    }
+
+    std::env::set_var("ENV_PREFIX_FROM_ENV_PORT", "8080");
+    assert_eq!(
+        Config {
+            port: 8080,
+            log_level: "info".to_string(),
+            files: vec![],
+        },
+        Config::from_env()
+    );
+}
+
+#[test]
+fn from_env_safe_errors() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    #[structopt(env_prefix = "ENV_PREFIX_MISSING_")]
+    struct Config {
+        #[structopt(long)]
+        port: u16,
+    }
+
+    let err = Config::from_env_safe().unwrap_err();
+    assert_eq!(ErrorKind::MissingRequiredArgument, err.kind);
+    assert!(err.message.contains("--port"));
+
+    #[derive(StructOpt, Debug, PartialEq)]
+    #[structopt(env_prefix = "ENV_PREFIX_INVALID_")]
+    struct Invalid {
+        #[structopt(long)]
+        port: u16,
+    }
+
+    std::env::set_var("ENV_PREFIX_INVALID_PORT", "many");
+    let err = Invalid::from_env_safe().unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+}
+
+#[test]
+fn command_line_still_wins() {
+    std::env::set_var("ENV_PREFIX_MAX_JOBS", "2");
+    let opt = Opt::from_iter(&["test", "--max-jobs", "4", "-v"]);
+    assert_eq!(4, opt.max_jobs);
+    assert!(opt.verbose);
+}
+
+#[test]
+fn flags_from_env() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    #[structopt(env_prefix = "ENV_PREFIX_FLAGS_")]
+    struct Config {
+        #[structopt(long)]
+        dry_run: bool,
+        #[structopt(short, parse(from_occurrences))]
+        verbose: u8,
+        #[structopt(long)]
+        color: bool,
+    }
+
+    std::env::set_var("ENV_PREFIX_FLAGS_DRY_RUN", "yes");
+    std::env::set_var("ENV_PREFIX_FLAGS_VERBOSE", "2");
+    std::env::set_var("ENV_PREFIX_FLAGS_COLOR", "off");
+    assert_eq!(
+        Config {
+            dry_run: true,
+            verbose: 2,
+            color: false,
+        },
+        Config::from_env()
+    );
+    assert_eq!(
+        Config {
+            dry_run: true,
+            verbose: 1,
+            color: true,
+        },
+        Config::from_iter(&["test", "-v", "--color"])
+    );
+
+    #[derive(StructOpt, Debug, PartialEq)]
+    #[structopt(env_prefix = "ENV_PREFIX_BAD_FLAG_")]
+    struct Bad {
+        #[structopt(long)]
+        dry_run: bool,
+    }
+
+    std::env::set_var("ENV_PREFIX_BAD_FLAG_DRY_RUN", "maybe");
+    let err = Bad::from_env_safe().unwrap_err();
+    assert_eq!(ErrorKind::InvalidValue, err.kind);
+    assert!(err.message.contains("ENV_PREFIX_BAD_FLAG_DRY_RUN"));
+    assert!(Bad::from_iter_safe(&["test", "--dry-run"]).unwrap().dry_run);
+}
@@ -0,0 +1,132 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::clap::ErrorKind;
+use structopt::{ErrorReport, StructOpt};
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(error_format = "json")]
+struct Opt {
+    #[structopt(long)]
+    max_depth: u32,
+    #[structopt(long, possible_values = &["fast", "slow"])]
+    mode: Option<String>,
+    #[structopt(long)]
+    verbose: bool,
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(error_format = "text")]
+enum Cmd {
+    Build,
+    Check,
+}
+
+fn error_report<T: StructOpt + std::fmt::Debug>(args: &[&str]) -> ErrorReport {
+    ErrorReport::new(&T::from_iter_safe(args).unwrap_err())
+}
+
+#[test]
+fn error_format_parses_as_usual() {
+    assert_eq!(
+        Opt {
+            max_depth: 3,
+            mode: None,
+            verbose: true,
+        },
+        Opt::from_iter(&["test", "--max-depth", "3", "--verbose"])
+    );
+    assert_eq!(Cmd::Check, Cmd::from_iter(&["test", "check"]));
+}
+
+#[test]
+fn value_validation_report() {
+    let report = error_report::<Opt>(&["test", "--max-depth", "ten"]);
+    assert_eq!(ErrorKind::ValueValidation, report.kind);
+    assert_eq!(Some("max-depth"), report.argument.as_deref());
+    assert_eq!(Some("ten"), report.value.as_deref());
+    assert!(report.suggestions.is_empty());
+    assert_eq!(
+        "Invalid value for '--max-depth <max-depth>': invalid digit found in string",
+        report.message
+    );
+}
+
+#[test]
+fn invalid_value_report() {
+    let report = error_report::<Opt>(&["test", "--max-depth", "1", "--mode", "fats"]);
+    assert_eq!(ErrorKind::InvalidValue, report.kind);
+    assert_eq!(Some("mode"), report.argument.as_deref());
+    assert_eq!(Some("fats"), report.value.as_deref());
+    assert_eq!(vec!["fast".to_string()], report.suggestions);
+}
+
+#[test]
+fn unknown_argument_report() {
+    let report = error_report::<Opt>(&["test", "--max-depth", "1", "--verbos"]);
+    assert_eq!(ErrorKind::UnknownArgument, report.kind);
+    assert_eq!(None, report.argument);
+    assert_eq!(Some("--verbos"), report.value.as_deref());
+    assert_eq!(vec!["--verbose".to_string()], report.suggestions);
+}
+
+#[test]
+fn invalid_subcommand_report() {
+    let report = error_report::<Cmd>(&["test", "chek"]);
+    assert_eq!(ErrorKind::InvalidSubcommand, report.kind);
+    assert_eq!(Some("chek"), report.value.as_deref());
+    assert_eq!(vec!["check".to_string()], report.suggestions);
+}
+
+#[test]
+fn report_to_json() {
+    let report = error_report::<Opt>(&["test", "--max-depth", "1", "--mode", "fats"]);
+    assert_eq!(
+        r#"{"kind":"InvalidValue","message":"'fats' isn't a valid value for '--mode <mode>'","argument":"mode","value":"fats","suggestions":["fast"]}"#,
+        report.to_json()
+    );
+
+    let report = error_report::<Opt>(&["test"]);
+    assert_eq!(ErrorKind::MissingRequiredArgument, report.kind);
+    assert!(report
+        .to_json()
+        .ends_with(r#""argument":null,"value":null,"suggestions":[]}"#));
+}
+
+#[test]
+fn clap_errors_have_no_recorded_value() {
+    let err = Opt::clap()
+        .get_matches_from_safe(&["test", "--max-depth", "ten"])
+        .unwrap_err();
+    let report = ErrorReport::new(&err);
+    assert_eq!(ErrorKind::ValueValidation, report.kind);
+    assert_eq!(None, report.argument);
+    assert_eq!(None, report.value);
+
+    // nothing from the error above leaks into the next parse
+    let report = error_report::<Opt>(&["test", "--max-depth", "1", "--mode", "x"]);
+    assert_eq!(ErrorKind::InvalidValue, report.kind);
+    assert_eq!(Some("x"), report.value.as_deref());
+    assert!(report.suggestions.is_empty());
+}
+
+#[test]
+fn subcommand_suggestions() {
+    #[derive(StructOpt, Debug)]
+    enum Tool {
+        Run {
+            #[structopt(long)]
+            release: bool,
+        },
+    }
+
+    let report = error_report::<Tool>(&["test", "run", "--relase"]);
+    assert_eq!(ErrorKind::UnknownArgument, report.kind);
+    assert_eq!(Some("--relase"), report.value.as_deref());
+    assert_eq!(vec!["--release".to_string()], report.suggestions);
+}
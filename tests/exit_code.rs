@@ -0,0 +1,139 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use structopt::clap::ErrorKind;
+use structopt::StructOpt;
+use utils::{run_example, run_in_child};
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(exit_code = structopt::sysexits)]
+struct Opt {
+    #[structopt(short, long)]
+    count: u32,
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(exit_code = |kind| match kind {
+    ErrorKind::ValueValidation => 3,
+    _ => 2,
+})]
+enum Cmd {
+    Add { name: String },
+    Remove { name: String },
+}
+
+#[test]
+fn sysexits_mapping() {
+    assert_eq!(0, structopt::sysexits(ErrorKind::HelpDisplayed));
+    assert_eq!(0, structopt::sysexits(ErrorKind::VersionDisplayed));
+    assert_eq!(64, structopt::sysexits(ErrorKind::MissingRequiredArgument));
+    assert_eq!(64, structopt::sysexits(ErrorKind::UnknownArgument));
+    assert_eq!(74, structopt::sysexits(ErrorKind::Io));
+    assert_eq!(70, structopt::sysexits(ErrorKind::Format));
+}
+
+#[test]
+fn exit_code_parses_as_usual() {
+    assert_eq!(Opt { count: 3 }, Opt::from_iter(&["test", "--count", "3"]));
+    assert_eq!(
+        Cmd::Remove {
+            name: "foo".to_string()
+        },
+        Cmd::from_iter(&["test", "remove", "foo"])
+    );
+}
+
+#[test]
+fn exit_code_keeps_safe_errors() {
+    let err = Opt::from_iter_safe(&["test"]).unwrap_err();
+    assert_eq!(ErrorKind::MissingRequiredArgument, err.kind);
+
+    let err = Cmd::from_iter_safe(&["test", "add"]).unwrap_err();
+    assert_eq!(ErrorKind::MissingRequiredArgument, err.kind);
+}
+
+#[test]
+fn exit_code_sets_process_status() {
+    let out = run_example("exit_code", &[]);
+    assert_eq!(out.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&out.stderr)
+        .starts_with("error: The following required arguments were not provided:"));
+
+    let out = run_example("exit_code", &["--count", "x"]);
+    assert_eq!(out.status.code(), Some(64));
+
+    let out = run_example("exit_code", &["--help"]);
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&out.stdout).starts_with("exit_code"));
+
+    let out = run_example("exit_code", &["--count", "3"]);
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&out.stdout), "Opt { count: 3 }\n");
+}
+
+#[test]
+fn from_line_exit_code() {
+    let out = run_in_child("from_line_exit_code", || {
+        Opt::from_line("test --count 'x");
+    });
+    assert_eq!(out.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("unterminated single quote"));
+}
+
+#[test]
+fn from_line_custom_exit_code() {
+    let out = run_in_child("from_line_custom_exit_code", || {
+        Cmd::from_line("test add");
+    });
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn update_from_iter_exit_code() {
+    let out = run_in_child("update_from_iter_exit_code", || {
+        Opt { count: 1 }.update_from_iter(&["test", "--count", "x"]);
+    });
+    assert_eq!(out.status.code(), Some(64));
+}
+
+#[test]
+fn from_env_exit_code() {
+    let out = run_in_child("from_env_exit_code", || {
+        Cmd::from_env();
+    });
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Wrapped(#[structopt(flatten)] Opt);
+
+#[test]
+fn newtype_from_line_exit_code() {
+    let out = run_in_child("newtype_from_line_exit_code", || {
+        Wrapped::from_line("test --count x");
+    });
+    assert_eq!(out.status.code(), Some(64));
+}
+
+#[test]
+fn newtype_update_from_iter_exit_code() {
+    let out = run_in_child("newtype_update_from_iter_exit_code", || {
+        Wrapped(Opt { count: 1 }).update_from_iter(&["test", "--count", "x"]);
+    });
+    assert_eq!(out.status.code(), Some(64));
+}
+
+#[test]
+fn newtype_from_env_exit_code() {
+    let out = run_in_child("newtype_from_env_exit_code", || {
+        Wrapped::from_env();
+    });
+    assert_eq!(out.status.code(), Some(64));
+}
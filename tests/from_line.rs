@@ -0,0 +1,93 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::clap::ErrorKind;
+use structopt::{split_line, StructOpt};
+
+#[derive(StructOpt, Debug, PartialEq)]
+enum Opt {
+    Add {
+        #[structopt(short, long)]
+        message: Option<String>,
+        files: Vec<String>,
+    },
+    Remove {
+        file: String,
+    },
+}
+
+#[test]
+fn split_blanks() {
+    assert_eq!(vec!["a", "b", "c"], split_line("  a\tb \n c ").unwrap());
+    assert_eq!(vec!["a", "b"], split_line("a b\r\n").unwrap());
+    assert_eq!(vec!["a", "b c"], split_line("a 'b c'\r\n").unwrap());
+    assert!(split_line("").unwrap().is_empty());
+    assert!(split_line(" \t").unwrap().is_empty());
+}
+
+#[test]
+fn split_quotes() {
+    assert_eq!(vec!["a b", "c"], split_line("'a b' c").unwrap());
+    assert_eq!(vec!["a b", "c"], split_line(r#""a b" c"#).unwrap());
+    assert_eq!(vec!["abc"], split_line(r#"a'b'"c""#).unwrap());
+    assert_eq!(vec!["", "x"], split_line(r#"'' "x""#).unwrap());
+    assert_eq!(vec![r#"a\b"#], split_line(r#"'a\b'"#).unwrap());
+}
+
+#[test]
+fn split_escapes() {
+    assert_eq!(vec!["a b"], split_line(r#"a\ b"#).unwrap());
+    assert_eq!(vec!["it's"], split_line(r#"it\'s"#).unwrap());
+    assert_eq!(vec!["ab"], split_line("a\\\nb").unwrap());
+    assert_eq!(
+        vec![r#"say "hi" \n $x"#],
+        split_line(r#""say \"hi\" \n \$x""#).unwrap()
+    );
+}
+
+#[test]
+fn split_no_expansion() {
+    assert_eq!(
+        vec!["$HOME", "~", "*.rs", "#comment"],
+        split_line("$HOME ~ *.rs #comment").unwrap()
+    );
+}
+
+#[test]
+fn split_errors() {
+    for line in &["'a", r#""a"#, r#""a\"#, "a\\"] {
+        let err = split_line(line).unwrap_err();
+        assert_eq!(ErrorKind::InvalidValue, err.kind, "{}", line);
+    }
+}
+
+#[test]
+fn from_line() {
+    assert_eq!(
+        Opt::Add {
+            message: Some("first commit".to_string()),
+            files: vec!["a file.txt".to_string(), "b".to_string()],
+        },
+        Opt::from_line(r#"git add -m "first commit" 'a file.txt' b"#)
+    );
+    assert_eq!(
+        Opt::Remove {
+            file: "a".to_string()
+        },
+        Opt::from_line_safe("git remove a").unwrap()
+    );
+}
+
+#[test]
+fn from_line_safe_errors() {
+    let err = Opt::from_line_safe("git remove 'a").unwrap_err();
+    assert_eq!(ErrorKind::InvalidValue, err.kind);
+
+    let err = Opt::from_line_safe("git remove").unwrap_err();
+    assert_eq!(ErrorKind::MissingRequiredArgument, err.kind);
+}
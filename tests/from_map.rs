@@ -0,0 +1,157 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::clap::ErrorKind;
+use structopt::map::Value;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(rename_all = "snake")]
+struct Opt {
+    #[structopt(short, long)]
+    verbose: bool,
+    #[structopt(short, parse(from_occurrences))]
+    quiet: u8,
+    #[structopt(long, default_value = "1")]
+    max_jobs: u32,
+    #[structopt(short)]
+    offset: Option<i32>,
+    #[structopt(long)]
+    tag: Vec<String>,
+    input: String,
+    output: Option<String>,
+    #[structopt(subcommand)]
+    cmd: Option<Cmd>,
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+enum Cmd {
+    Build {
+        #[structopt(long)]
+        release: bool,
+        target: Option<String>,
+    },
+}
+
+fn map(entries: Vec<(&str, Value)>) -> Result<Opt, structopt::clap::Error> {
+    Opt::from_map(entries)
+}
+
+#[test]
+fn defaults_and_required() {
+    assert_eq!(
+        Opt {
+            verbose: false,
+            quiet: 0,
+            max_jobs: 1,
+            offset: None,
+            tag: vec![],
+            input: "in".to_string(),
+            output: None,
+            cmd: None,
+        },
+        map(vec![("input", "in".into())]).unwrap()
+    );
+
+    let err = map(vec![]).unwrap_err();
+    assert_eq!(ErrorKind::MissingRequiredArgument, err.kind);
+}
+
+#[test]
+fn all_kinds_of_arguments() {
+    assert_eq!(
+        Opt {
+            verbose: true,
+            quiet: 3,
+            max_jobs: 8,
+            offset: Some(-5),
+            tag: vec!["a".to_string(), "b".to_string()],
+            input: "-in".to_string(),
+            output: Some("out".to_string()),
+            cmd: None,
+        },
+        map(vec![
+            ("verbose", true.into()),
+            ("quiet", 3.into()),
+            ("max_jobs", 8.into()),
+            ("offset", (-5).into()),
+            ("tag", vec!["a", "b"].into()),
+            ("output", "out".into()),
+            ("input", "-in".into()),
+        ])
+        .unwrap()
+    );
+}
+
+#[test]
+fn false_flag() {
+    let opt = map(vec![("verbose", false.into()), ("input", "in".into())]).unwrap();
+    assert!(!opt.verbose);
+}
+
+#[test]
+fn subcommand() {
+    let opt = map(vec![
+        ("input", "in".into()),
+        (
+            "build",
+            Value::Map(vec![
+                ("release".to_string(), true.into()),
+                ("target".to_string(), "arm".into()),
+            ]),
+        ),
+    ])
+    .unwrap();
+    assert_eq!(
+        Some(Cmd::Build {
+            release: true,
+            target: Some("arm".to_string())
+        }),
+        opt.cmd
+    );
+}
+
+#[test]
+fn parse_errors() {
+    let err = map(vec![("input", "in".into()), ("max_jobs", "many".into())]).unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+
+    let err = map(vec![("input", "in".into()), ("foo", true.into())]).unwrap_err();
+    assert_eq!(ErrorKind::UnknownArgument, err.kind);
+
+    let err = map(vec![("input", "in".into()), ("verbose", "yes".into())]).unwrap_err();
+    assert_eq!(ErrorKind::InvalidValue, err.kind);
+
+    let err = map(vec![("input", vec![1, 2].into())]).unwrap_err();
+    assert_eq!(ErrorKind::InvalidValue, err.kind);
+
+    let err = map(vec![("output", "out".into())]).unwrap_err();
+    assert_eq!(ErrorKind::MissingRequiredArgument, err.kind);
+}
+
+#[test]
+fn ambiguous_positional_with_subcommand() {
+    let build = || Value::Map(vec![("release".to_string(), true.into())]);
+
+    let err = map(vec![("input", "--verbose".into()), ("build", build())]).unwrap_err();
+    assert_eq!(ErrorKind::InvalidValue, err.kind);
+    assert!(err.message.contains("'--verbose'"), "{}", err.message);
+
+    let err = map(vec![
+        ("input", "in".into()),
+        ("output", "build".into()),
+        ("build", build()),
+    ])
+    .unwrap_err();
+    assert_eq!(ErrorKind::InvalidValue, err.kind);
+
+    // without a subcommand, a `--` keeps them positional
+    let opt = map(vec![("input", "--verbose".into())]).unwrap();
+    assert!(!opt.verbose);
+    assert_eq!("--verbose", opt.input);
+}
@@ -0,0 +1,97 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use std::fmt;
+
+use structopt::__private::MainResult;
+use structopt::{Failure, MainError, StructOpt};
+use utils::run_example;
+
+#[derive(StructOpt, Debug)]
+struct Opt {
+    #[structopt(short)]
+    fail: bool,
+}
+
+#[derive(Debug)]
+struct Unavailable;
+
+impl fmt::Display for Unavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "service unavailable")
+    }
+}
+
+impl MainError for Unavailable {
+    fn exit_code(&self) -> i32 {
+        69
+    }
+}
+
+#[structopt::main]
+fn entry(opt: Opt) -> Result<(), Unavailable> {
+    if opt.fail {
+        Err(Unavailable)
+    } else {
+        Ok(())
+    }
+}
+
+#[structopt::main]
+fn entry_unit(_opt: Opt) {}
+
+#[test]
+fn main_signature() {
+    let _: fn() = entry;
+    let _: fn() = entry_unit;
+}
+
+#[test]
+fn main_result_exit_code() {
+    assert_eq!(().report(), 0);
+    assert_eq!(Ok::<(), Unavailable>(()).report(), 0);
+    assert_eq!(Err::<(), _>(Unavailable).report(), 69);
+    assert_eq!(Err::<(), _>("oops").report(), 1);
+
+    let help = Opt::from_iter_safe(&["test", "--help"]).unwrap_err();
+    assert_eq!(help.exit_code(), 0);
+    let err = Opt::from_iter_safe(&["test", "--bad"]).unwrap_err();
+    assert_eq!(err.exit_code(), 1);
+}
+
+#[test]
+fn failure_from_display() {
+    fn fallible() -> Result<(), Failure> {
+        "x".parse::<u32>()?;
+        Ok(())
+    }
+
+    let failure = fallible().unwrap_err();
+    assert_eq!(failure.code(), 1);
+    assert_eq!(failure.message(), "invalid digit found in string");
+    assert_eq!(Err::<(), _>(failure).report(), 1);
+    assert_eq!(Err::<(), _>(Failure::new(65, "empty input")).report(), 65);
+}
+
+#[test]
+fn wrapped_main_exit_status() {
+    let out = run_example("main_attr", &["--retries", "0"]);
+    assert_eq!(out.status.code(), Some(75));
+    assert_eq!(
+        String::from_utf8_lossy(&out.stderr),
+        "error: giving up after 0 retries\n"
+    );
+
+    let out = run_example("main_attr", &["--retries", "2"]);
+    assert_eq!(out.status.code(), Some(0));
+
+    let out = run_example("main_attr", &["--retries", "x"]);
+    assert_eq!(out.status.code(), Some(1));
+}
@@ -0,0 +1,89 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::StructOpt;
+
+#[derive(StructOpt, PartialEq, Debug)]
+#[structopt(multicall, rename_all = "snake")]
+enum Applet {
+    #[structopt(alias = "dir")]
+    Ls {
+        #[structopt(short)]
+        all: bool,
+    },
+    #[structopt(aliases = &["concat", "type"])]
+    CatFile { file: String },
+    #[structopt(flatten)]
+    Net(Net),
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+enum Net {
+    Ping { host: String },
+}
+
+#[test]
+fn dispatch_on_argv0() {
+    assert_eq!(
+        Applet::Ls { all: true },
+        Applet::from_iter(&["/usr/bin/ls", "-a"])
+    );
+    assert_eq!(
+        Applet::CatFile { file: "foo".into() },
+        Applet::from_iter(&["cat_file", "foo"])
+    );
+    assert_eq!(
+        Applet::Net(Net::Ping {
+            host: "localhost".into()
+        }),
+        Applet::from_iter(&["ping", "localhost"])
+    );
+}
+
+#[test]
+fn dispatch_on_alias() {
+    assert_eq!(Applet::Ls { all: false }, Applet::from_iter(&["bin/dir"]));
+    assert_eq!(
+        Applet::CatFile { file: "foo".into() },
+        Applet::from_iter(&["type.exe", "foo"])
+    );
+}
+
+#[test]
+fn fallback_to_subcommand() {
+    assert_eq!(
+        Applet::Ls { all: true },
+        Applet::from_iter(&["busybox", "ls", "-a"])
+    );
+    assert!(Applet::from_iter_safe(&["busybox", "-a"]).is_err());
+}
+
+#[test]
+fn update_dispatches_on_argv0() {
+    let mut applet = Applet::Ls { all: false };
+    applet.update_from_iter(&["/usr/bin/ls", "-a"]);
+    assert_eq!(Applet::Ls { all: true }, applet);
+
+    applet.update_from_iter_safe(&["cat_file", "foo"]).unwrap();
+    assert_eq!(Applet::CatFile { file: "foo".into() }, applet);
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Wrapped(#[structopt(flatten)] Applet);
+
+#[test]
+fn newtype_dispatches_on_argv0() {
+    let mut wrapped = Wrapped::from_iter(&["/usr/bin/ls"]);
+    assert_eq!(Wrapped(Applet::Ls { all: false }), wrapped);
+
+    wrapped.update_from_iter(&["/usr/bin/ls", "-a"]);
+    assert_eq!(Wrapped(Applet::Ls { all: true }), wrapped);
+
+    let wrapped = Wrapped::from_line("cat_file foo");
+    assert_eq!(Wrapped(Applet::CatFile { file: "foo".into() }), wrapped);
+}
@@ -0,0 +1,243 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::clap::ErrorKind;
+use structopt::parsers::{self, byte_size, duration, int, ranges, ByteSize, Ranges};
+use structopt::StructOpt;
+
+use std::time::Duration;
+
+#[test]
+fn durations() {
+    assert_eq!(duration("1h30m"), Ok(Duration::from_secs(5400)));
+    assert_eq!(duration("250ms"), Ok(Duration::from_millis(250)));
+    assert_eq!(duration("1.5 days"), Ok(Duration::from_secs(129_600)));
+    assert_eq!(duration("2m 3s"), Ok(Duration::from_secs(123)));
+    assert_eq!(duration("10us"), Ok(Duration::from_micros(10)));
+    assert_eq!(duration("10µs"), Ok(Duration::from_micros(10)));
+    assert_eq!(duration("7ns"), Ok(Duration::from_nanos(7)));
+    assert_eq!(duration(".5s"), Ok(Duration::from_millis(500)));
+    assert_eq!(duration("0s"), Ok(Duration::from_secs(0)));
+
+    assert!(duration("").is_err());
+    assert!(duration("10").is_err());
+    assert!(duration("h").is_err());
+    assert!(duration("1.2.3s").is_err());
+    assert!(duration("-1s").is_err());
+    assert!(duration("99999999999999999999d").is_err());
+    assert_eq!(
+        duration("3 fortnights").unwrap_err().to_string(),
+        "unknown unit `fortnights` in `3 fortnights`, expected one of ns, us, ms, s, m, h, d"
+    );
+}
+
+#[test]
+fn byte_sizes() {
+    assert_eq!(byte_size("512"), Ok(512));
+    assert_eq!(byte_size("512B"), Ok(512));
+    assert_eq!(byte_size("4k"), Ok(4000));
+    assert_eq!(byte_size("4KiB"), Ok(4096));
+    assert_eq!(byte_size("10MiB"), Ok(10 << 20));
+    assert_eq!(byte_size("10 mb"), Ok(10_000_000));
+    assert_eq!(byte_size("1.5G"), Ok(1_500_000_000));
+    assert_eq!(byte_size("0.5Ki"), Ok(512));
+    assert_eq!(byte_size("1.0001K"), Ok(1000));
+
+    assert!(byte_size("").is_err());
+    assert!(byte_size("MiB").is_err());
+    assert!(byte_size("16EiB").is_err());
+    assert_eq!(
+        byte_size("3 bits").unwrap_err().to_string(),
+        "unknown unit `bits` in `3 bits`, expected one of B, K, KiB, M, MiB, G, GiB, T, TiB, \
+         P, PiB, E, EiB"
+    );
+    assert_eq!("2M".parse(), Ok(ByteSize(2_000_000)));
+}
+
+#[test]
+fn integers() {
+    assert_eq!(int::<u32>("42"), Ok(42));
+    assert_eq!(int::<u32>("0xff"), Ok(255));
+    assert_eq!(int::<u32>("0XFF"), Ok(255));
+    assert_eq!(int::<u32>("0o755"), Ok(0o755));
+    assert_eq!(int::<u8>("0b1010_1010"), Ok(0b1010_1010));
+    assert_eq!(int::<i64>("-1_000_000"), Ok(-1_000_000));
+    assert_eq!(int::<i32>("-0x10"), Ok(-16));
+    assert_eq!(int::<u16>("+7"), Ok(7));
+
+    assert!(int::<u8>("256").is_err());
+    assert!(int::<u8>("-1").is_err());
+    assert!(int::<u32>("0x").is_err());
+    assert!(int::<u32>("0b102").is_err());
+    assert!(int::<i32>("0x-5").is_err());
+    assert!(int::<i32>("0b+1").is_err());
+    assert!(int::<i32>("--5").is_err());
+    assert!(int::<u32>("").is_err());
+}
+
+#[test]
+fn integer_ranges() {
+    let cpus = ranges::<u16>("1-5,9").unwrap();
+    assert_eq!(cpus.ranges(), [1..=5, 9..=9]);
+    assert_eq!(cpus.iter().collect::<Vec<_>>(), [1, 2, 3, 4, 5, 9]);
+    assert!(cpus.contains(&3));
+    assert!(!cpus.contains(&7));
+
+    assert_eq!(
+        ranges::<i32>("-3--1, 0x10").unwrap().ranges(),
+        [-3..=-1, 16..=16]
+    );
+    assert_eq!(
+        ranges::<u8>("254-255").unwrap().iter().collect::<Vec<_>>(),
+        [254, 255]
+    );
+
+    assert!(ranges::<u8>("").is_err());
+    assert!(ranges::<u8>("1,,2").is_err());
+    assert!(ranges::<u8>("1-").is_err());
+    assert!(ranges::<u8>("1-300").is_err());
+    assert_eq!(
+        ranges::<u8>("5-1").unwrap_err().to_string(),
+        "`5-1` is not a range, its start is after its end"
+    );
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+struct Opt {
+    #[structopt(long)]
+    timeout: Duration,
+
+    #[structopt(long)]
+    retry: Option<std::time::Duration>,
+
+    #[structopt(long)]
+    backoff: Vec<Duration>,
+
+    #[structopt(long, default_value = "1MiB")]
+    max_size: ByteSize,
+
+    #[structopt(long, parse(try_from_str = parsers::byte_size))]
+    block_size: Option<u64>,
+
+    #[structopt(long, parse(try_from_str = parsers::int), default_value = "0o644")]
+    mode: u32,
+
+    #[structopt(long)]
+    cpus: Option<Ranges<u16>>,
+}
+
+#[test]
+fn parsers_in_derive() {
+    assert_eq!(
+        Opt {
+            timeout: Duration::from_secs(90),
+            retry: Some(Duration::from_millis(250)),
+            backoff: vec![Duration::from_secs(1), Duration::from_secs(60)],
+            max_size: ByteSize(1 << 20),
+            block_size: Some(4096),
+            mode: 0o644,
+            cpus: None,
+        },
+        Opt::from_iter(&[
+            "test",
+            "--timeout",
+            "1m30s",
+            "--retry",
+            "250ms",
+            "--backoff",
+            "1s",
+            "--backoff",
+            "1m",
+            "--block-size",
+            "4KiB",
+        ])
+    );
+
+    let opt = Opt::from_iter(&[
+        "test",
+        "--timeout",
+        "1s",
+        "--mode",
+        "0o755",
+        "--cpus",
+        "0-3,8",
+    ]);
+    assert_eq!(opt.mode, 0o755);
+    assert_eq!(
+        opt.cpus.unwrap().iter().collect::<Vec<_>>(),
+        [0, 1, 2, 3, 8]
+    );
+}
+
+#[test]
+fn parser_errors_in_derive() {
+    let err = Opt::from_iter_safe(&["test", "--timeout", "10"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+    assert!(
+        err.message
+            .contains("missing a unit after `10` in `10`, like `10s`"),
+        "{}",
+        err.message
+    );
+
+    let err = Opt::from_iter_safe(&["test", "--timeout", "1s", "--max-size", "big"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+}
+
+#[test]
+fn custom_parser_for_duration() {
+    fn seconds(s: &str) -> Result<Duration, std::num::ParseIntError> {
+        s.parse().map(Duration::from_secs)
+    }
+
+    #[derive(StructOpt, PartialEq, Debug)]
+    struct Opt {
+        #[structopt(long, parse(try_from_str = seconds))]
+        timeout: Duration,
+    }
+
+    assert_eq!(
+        Opt {
+            timeout: Duration::from_secs(10)
+        },
+        Opt::from_iter(&["test", "--timeout", "10"])
+    );
+}
+
+mod my {
+    /// A duration in whole seconds, with its own `FromStr`.
+    #[derive(PartialEq, Debug)]
+    pub struct Duration(pub u64);
+
+    impl std::str::FromStr for Duration {
+        type Err = std::num::ParseIntError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            s.parse().map(Duration)
+        }
+    }
+}
+
+#[test]
+fn other_durations_use_from_str() {
+    #[derive(StructOpt, PartialEq, Debug)]
+    struct Opt {
+        #[structopt(long)]
+        timeout: my::Duration,
+        #[structopt(long)]
+        delay: core::time::Duration,
+    }
+
+    assert_eq!(
+        Opt {
+            timeout: my::Duration(10),
+            delay: Duration::from_millis(5),
+        },
+        Opt::from_iter(&["test", "--timeout", "10", "--delay", "5ms"])
+    );
+}
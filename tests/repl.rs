@@ -0,0 +1,108 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::repl::Repl;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq)]
+enum Command {
+    /// Push a value on the stack
+    Push { value: i64 },
+    /// Pop a value from the stack
+    Pop,
+    Print {
+        #[structopt(short, long)]
+        all: bool,
+    },
+}
+
+fn run(input: &str) -> (Vec<Command>, String) {
+    let mut output = Vec::new();
+    let commands = Repl::new(input.as_bytes(), &mut output)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    (commands, String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn reads_commands() {
+    let (commands, output) = run("push 1\n\n  pop \nprint --all\nprint");
+    assert_eq!(
+        vec![
+            Command::Push { value: 1 },
+            Command::Pop,
+            Command::Print { all: true },
+            Command::Print { all: false },
+        ],
+        commands
+    );
+    assert_eq!("", output);
+}
+
+#[test]
+fn errors_do_not_stop() {
+    let (commands, output) = run("push x\npush 'unterminated\npop --all\nfoo\npop\n");
+    assert_eq!(vec![Command::Pop], commands);
+    assert!(output.contains("Invalid value for '<value>'"));
+    assert!(output.contains("unterminated single quote"));
+    assert!(output.contains("Found argument '--all'"));
+    assert!(output.contains("Found argument 'foo'"));
+}
+
+#[test]
+fn help_builtin() {
+    let (commands, output) = run("help\nhelp push\npush --help\npop\n");
+    assert_eq!(vec![Command::Pop], commands);
+    assert!(output.contains("Push a value on the stack"));
+    assert_eq!(4, output.matches("<value>").count());
+}
+
+#[test]
+fn exit_builtin() {
+    let (commands, _) = run("pop\nexit\npop\n");
+    assert_eq!(vec![Command::Pop], commands);
+}
+
+#[test]
+fn prompt() {
+    let mut output = Vec::new();
+    let mut repl = Repl::<Command, _, _>::new("pop\n".as_bytes(), &mut output).prompt("> ");
+    assert_eq!(Command::Pop, repl.next_command().unwrap().unwrap());
+    assert!(repl.next_command().unwrap().is_none());
+    drop(repl);
+    assert_eq!("> > ", String::from_utf8(output).unwrap());
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Args {
+    #[structopt(short)]
+    verbose: bool,
+    name: String,
+}
+
+#[test]
+fn struct_commands() {
+    let mut output = Vec::new();
+    let args: Vec<Args> = Repl::new("-v 'a b'\nc\nhelp\n".as_bytes(), &mut output)
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        vec![
+            Args {
+                verbose: true,
+                name: "a b".to_string()
+            },
+            Args {
+                verbose: false,
+                name: "c".to_string()
+            },
+        ],
+        args
+    );
+    assert!(String::from_utf8(output).unwrap().contains("USAGE:"));
+}
@@ -0,0 +1,118 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use std::cell::RefCell;
+
+use structopt::{StructOpt, StructOptRun};
+
+#[derive(Default)]
+struct Ctx {
+    log: RefCell<Vec<String>>,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(run(context = Ctx, output = Result<u32, String>))]
+enum Cmd {
+    #[structopt(run = add)]
+    Add {
+        name: String,
+    },
+    #[structopt(run = |ctx: &Ctx| Ok(ctx.log.borrow().len() as u32))]
+    Count,
+    #[structopt(run = copy)]
+    Copy(String, String),
+    Remote(Remote),
+    #[structopt(flatten)]
+    Other(Other),
+}
+
+fn add(name: String, ctx: &Ctx) -> Result<u32, String> {
+    ctx.log.borrow_mut().push(name);
+    Ok(0)
+}
+
+fn copy(from: String, to: String, ctx: &Ctx) -> Result<u32, String> {
+    ctx.log.borrow_mut().push(format!("{} -> {}", from, to));
+    Ok(1)
+}
+
+#[derive(StructOpt, Debug)]
+struct Remote {
+    #[structopt(short)]
+    verbose: bool,
+}
+
+impl StructOptRun<Ctx> for Remote {
+    type Output = Result<u32, String>;
+
+    fn run(self, _ctx: &Ctx) -> Self::Output {
+        if self.verbose {
+            Err("verbose".into())
+        } else {
+            Ok(2)
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(run(context = Ctx, output = Result<u32, String>))]
+enum Other {
+    #[structopt(run = |_: &Ctx| Ok(3))]
+    Status,
+}
+
+#[test]
+fn run_handlers() {
+    let ctx = Ctx::default();
+
+    assert_eq!(Cmd::from_iter(&["test", "add", "foo"]).run(&ctx), Ok(0));
+    assert_eq!(Cmd::from_iter(&["test", "copy", "a", "b"]).run(&ctx), Ok(1));
+    assert_eq!(Cmd::from_iter(&["test", "count"]).run(&ctx), Ok(2));
+    assert_eq!(*ctx.log.borrow(), vec!["foo", "a -> b"]);
+}
+
+#[test]
+fn run_payloads() {
+    let ctx = Ctx::default();
+
+    assert_eq!(Cmd::from_iter(&["test", "remote"]).run(&ctx), Ok(2));
+    assert_eq!(
+        Cmd::from_iter(&["test", "remote", "-v"]).run(&ctx),
+        Err("verbose".into())
+    );
+    assert_eq!(Cmd::from_iter(&["test", "status"]).run(&ctx), Ok(3));
+}
+
+#[test]
+fn run_default_context() {
+    #[derive(StructOpt, Debug)]
+    #[structopt(run)]
+    enum Cmd {
+        #[structopt(run = |_: &()| ())]
+        Noop,
+    }
+
+    Cmd::from_iter(&["test", "noop"]).run(&());
+}
+
+#[test]
+fn run_field_named_ctx() {
+    #[derive(StructOpt, Debug)]
+    #[structopt(run(context = Ctx, output = String))]
+    enum Cmd {
+        #[structopt(run = |ctx: String, log: &Ctx| {
+            log.log.borrow_mut().push(ctx.clone());
+            ctx
+        })]
+        Use { ctx: String },
+    }
+
+    let log = Ctx::default();
+    assert_eq!(Cmd::from_iter(&["test", "use", "prod"]).run(&log), "prod");
+    assert_eq!(*log.log.borrow(), vec!["prod"]);
+}
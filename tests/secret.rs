@@ -0,0 +1,323 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use std::path::PathBuf;
+
+use structopt::clap::ErrorKind;
+use structopt::{ErrorReport, ParseContext, Secret, StructOpt};
+use utils::*;
+
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join("structopt-env-file-tests");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn secret_debug() {
+    #[derive(StructOpt, Debug)]
+    struct Opt {
+        #[structopt(long)]
+        user: String,
+        #[structopt(long)]
+        password: Secret<String>,
+        #[structopt(long)]
+        tokens: Vec<Secret<String>>,
+    }
+
+    let opt = Opt::from_iter(&[
+        "test",
+        "--user",
+        "me",
+        "--password",
+        "hunter2",
+        "--tokens",
+        "abc",
+    ]);
+    assert_eq!("hunter2", opt.password.expose());
+    assert_eq!("abc", opt.tokens[0].expose());
+    assert_eq!(
+        r#"Opt { user: "me", password: ***, tokens: [***] }"#,
+        format!("{:?}", opt)
+    );
+}
+
+#[test]
+fn secret_hidden_from_help() {
+    #[derive(StructOpt, Debug)]
+    struct Opt {
+        #[structopt(long, env = "SECRET_TEST_HELP_PASSWORD", default_value = "changeme")]
+        password: Secret<String>,
+        #[structopt(long, env = "SECRET_TEST_HELP_TOKEN")]
+        token: Option<structopt::Secret<String>>,
+        #[structopt(long, env = "SECRET_TEST_HELP_USER")]
+        user: String,
+    }
+
+    std::env::set_var("SECRET_TEST_HELP_PASSWORD", "hunter2");
+    std::env::set_var("SECRET_TEST_HELP_TOKEN", "abc123");
+    std::env::set_var("SECRET_TEST_HELP_USER", "me");
+    let help = get_help::<Opt>();
+    assert!(!help.contains("hunter2"));
+    assert!(!help.contains("changeme"));
+    assert!(!help.contains("abc123"));
+    assert!(help.contains("[env: SECRET_TEST_HELP_USER=me]"));
+}
+
+#[test]
+fn secret_hidden_from_errors() {
+    #[derive(StructOpt, Debug)]
+    struct Opt {
+        #[structopt(long)]
+        pin: Secret<std::net::IpAddr>,
+        #[structopt(long, parse(try_from_str = parse_key))]
+        key: Option<Secret<String>>,
+    }
+
+    fn parse_key(s: &str) -> Result<Secret<String>, String> {
+        Err(format!("'{}' is not a valid key", s))
+    }
+
+    let err = Opt::from_iter_safe(&["test", "--pin", "1234"]).unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+    let report = ErrorReport::new(&err);
+    assert_eq!(Some("***"), report.value.as_deref());
+
+    let err = Opt::from_iter_safe(&["test", "--pin", "::1", "--key", "s3cr3t"]).unwrap_err();
+    assert!(!err.message.contains("s3cr3t"));
+    assert_eq!(Some("***"), ErrorReport::new(&err).value.as_deref());
+}
+
+#[test]
+fn secret_from_str_redacts() {
+    #[derive(Debug)]
+    struct Echo;
+
+    impl std::str::FromStr for Echo {
+        type Err = String;
+
+        fn from_str(s: &str) -> Result<Self, String> {
+            Err(format!("bad value: {}", s))
+        }
+    }
+
+    let err = "s3cr3t".parse::<Secret<Echo>>().unwrap_err();
+    assert_eq!("the value isn't shown, as it's secret", err);
+    assert_eq!(Secret::new(42), "42".parse::<Secret<u32>>().unwrap());
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Db {
+    #[structopt(long, env = "ENV_FILE_TEST_PASSWORD", env_file)]
+    password: Option<String>,
+}
+
+#[test]
+fn env_file() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    #[structopt(env_prefix = "ENV_FILE_TEST_")]
+    struct Opt {
+        #[structopt(long, env_file)]
+        token: Secret<String>,
+    }
+
+    let path = temp_file("token", "  t0k3n\n");
+    std::env::set_var("ENV_FILE_TEST_TOKEN_FILE", &path);
+    assert_eq!("t0k3n", Opt::from_iter(&["test"]).token.expose());
+    assert_eq!(
+        "cli",
+        Opt::from_iter(&["test", "--token", "cli"]).token.expose()
+    );
+}
+
+#[test]
+fn env_file_precedence() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    struct Opt {
+        #[structopt(long, env = "ENV_FILE_TEST_BOTH", env_file)]
+        value: String,
+    }
+
+    let path = temp_file("both", "from-file");
+    std::env::set_var("ENV_FILE_TEST_BOTH_FILE", &path);
+    std::env::set_var("ENV_FILE_TEST_BOTH", "from-env");
+    assert_eq!("from-env", Opt::from_iter(&["test"]).value);
+}
+
+#[test]
+fn env_file_unset() {
+    assert_eq!(Db { password: None }, Db::from_iter(&["test"]));
+}
+
+#[test]
+fn env_file_unreadable() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    struct Opt {
+        #[structopt(long, env = "ENV_FILE_TEST_MISSING", env_file)]
+        port: Option<u16>,
+    }
+
+    std::env::set_var("ENV_FILE_TEST_MISSING_FILE", "/no/such/file");
+    let err = Opt::from_iter_safe(&["test"]).unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+    assert!(err.message.contains("/no/such/file"));
+    assert!(err.message.contains("ENV_FILE_TEST_MISSING_FILE"));
+
+    // the file isn't needed when the argument is given
+    assert_eq!(
+        Opt { port: Some(80) },
+        Opt::from_iter_safe(&["test", "--port", "80"]).unwrap()
+    );
+    // and the path is never taken as a value
+    assert_eq!(
+        Opt { port: None },
+        Opt::from_clap(&Opt::clap().get_matches_from(&["test"]))
+    );
+
+    #[derive(StructOpt, Debug, PartialEq)]
+    struct Required {
+        #[structopt(long, env = "ENV_FILE_TEST_MISSING_REQUIRED", env_file)]
+        port: u16,
+    }
+
+    std::env::set_var("ENV_FILE_TEST_MISSING_REQUIRED_FILE", "/no/such/file");
+    let err = Required::from_iter_safe(&["test"]).unwrap_err();
+    assert_eq!(ErrorKind::MissingRequiredArgument, err.kind);
+}
+
+#[test]
+fn env_file_hidden_from_help() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    struct Opt {
+        #[structopt(long, env = "ENV_FILE_TEST_HELP", env_file)]
+        password: Option<String>,
+    }
+
+    let path = temp_file("help", "hunter2");
+    std::env::set_var("ENV_FILE_TEST_HELP_FILE", &path);
+    let help = get_long_help::<Opt>();
+    assert!(help.contains("[env: ENV_FILE_TEST_HELP]"), "{}", help);
+    assert!(!help.contains("hunter2"));
+    assert_eq!(
+        Some("hunter2"),
+        Opt::from_iter(&["test"]).password.as_deref()
+    );
+}
+
+#[test]
+fn sensitive_hidden_from_help() {
+    #[derive(StructOpt, Debug)]
+    struct Opt {
+        #[structopt(long, env = "SENSITIVE_TEST_API_KEY", default_value = "k3y", sensitive)]
+        api_key: String,
+    }
+
+    std::env::set_var("SENSITIVE_TEST_API_KEY", "s3cr3t");
+    let help = get_help::<Opt>();
+    assert!(help.contains("SENSITIVE_TEST_API_KEY"));
+    assert!(!help.contains("s3cr3t"));
+    assert!(!help.contains("k3y"));
+}
+
+#[test]
+fn sensitive_hidden_from_errors() {
+    #[derive(StructOpt, Debug)]
+    struct Opt {
+        #[structopt(long, sensitive, parse(try_from_str = parse_key))]
+        api_key: Option<String>,
+        #[structopt(long, sensitive, parse(try_from_os_str = parse_os_key))]
+        os_key: Option<String>,
+    }
+
+    fn parse_key(s: &str) -> Result<String, String> {
+        Err(format!("'{}' is not a valid key", s))
+    }
+
+    fn parse_os_key(s: &std::ffi::OsStr) -> Result<String, std::ffi::OsString> {
+        let mut err = s.to_os_string();
+        err.push(" is not a valid key");
+        Err(err)
+    }
+
+    let err = Opt::from_iter_safe(&["test", "--api-key", "s3cr3t"]).unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+    assert!(!err.message.contains("s3cr3t"));
+    assert!(
+        !err.message.contains("is not a valid key"),
+        "{}",
+        err.message
+    );
+    assert_eq!(Some("***"), ErrorReport::new(&err).value.as_deref());
+
+    let err = Opt::from_iter_safe(&["test", "--os-key", "s3cr3t"]).unwrap_err();
+    assert!(!err.message.contains("s3cr3t"));
+    assert!(
+        !err.message.contains("is not a valid key"),
+        "{}",
+        err.message
+    );
+    assert_eq!(Some("***"), ErrorReport::new(&err).value.as_deref());
+}
+
+#[test]
+fn sensitive_range_and_len() {
+    #[derive(StructOpt, Debug)]
+    struct Opt {
+        #[structopt(long, sensitive, range = 1000..=9999)]
+        pin: Option<u32>,
+        #[structopt(long, sensitive, len = 8..)]
+        passphrase: Option<String>,
+    }
+
+    let err = Opt::from_iter_safe(&["test", "--pin", "123456"]).unwrap_err();
+    assert!(!err.message.contains("123456"), "{}", err.message);
+    assert_eq!(Some("***"), ErrorReport::new(&err).value.as_deref());
+
+    let err = Opt::from_iter_safe(&["test", "--passphrase", "hunter2"]).unwrap_err();
+    assert!(!err.message.contains("hunter2"), "{}", err.message);
+}
+
+#[test]
+fn sensitive_value_in_error_text() {
+    #[derive(StructOpt, Debug)]
+    struct Opt {
+        #[structopt(long, sensitive, parse(try_from_str = parse_token))]
+        token: Option<String>,
+        #[structopt(long, sensitive, range = 10..=20)]
+        level: Option<u32>,
+        #[structopt(long, parse(try_from_str = |s: &str, _: &ParseContext<'_>| {
+            parse_token(s).map(Secret::new)
+        }, context))]
+        key: Option<Secret<String>>,
+    }
+
+    fn parse_token(s: &str) -> Result<String, String> {
+        Err(format!("bad token {}: expected hex", s))
+    }
+
+    let expected = "error: Invalid value for '--token <token>': \
+                    the value isn't shown, as the argument is sensitive";
+    let err = Opt::from_iter_safe(&["test", "--token", "e"]).unwrap_err();
+    assert_eq!(expected, err.message.lines().next().unwrap());
+
+    let err = Opt::from_iter_safe(&["test", "--level", "2"]).unwrap_err();
+    assert_eq!(
+        expected.replace("--token <token>", "--level <level>"),
+        err.message.lines().next().unwrap()
+    );
+
+    let err = Opt::from_iter_safe(&["test", "--key", "e"]).unwrap_err();
+    assert_eq!(
+        expected.replace("--token <token>", "key"),
+        err.message.lines().next().unwrap()
+    );
+}
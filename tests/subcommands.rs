@@ -167,6 +167,39 @@ fn test_tuple_commands() {
     assert!(!output.contains("Not shown"));
 }
 
+#[derive(StructOpt, PartialEq, Debug)]
+enum Opt5 {
+    Copy(
+        /// File to copy
+        String,
+        /// Where to put the copy
+        #[structopt(name = "DEST")]
+        String,
+    ),
+    Rename(String, String, #[structopt(short = "f")] bool),
+}
+
+#[test]
+fn test_multi_field_tuple_commands() {
+    assert_eq!(
+        Opt5::Copy("a".to_string(), "b".to_string()),
+        Opt5::from_clap(&Opt5::clap().get_matches_from(&["test", "copy", "a", "b"]))
+    );
+    assert_eq!(
+        Opt5::Rename("a".to_string(), "b".to_string(), true),
+        Opt5::from_clap(&Opt5::clap().get_matches_from(&["test", "rename", "-f", "a", "b"]))
+    );
+    assert!(Opt5::clap()
+        .get_matches_from_safe(&["test", "copy", "a"])
+        .is_err());
+
+    let output = get_subcommand_long_help::<Opt5>("copy");
+
+    assert!(output.contains("<arg0> <DEST>"));
+    assert!(output.contains("File to copy"));
+    assert!(output.contains("Where to put the copy"));
+}
+
 #[test]
 fn enum_in_enum_subsubcommand() {
     #[derive(StructOpt, Debug, PartialEq)]
@@ -347,3 +380,86 @@ fn skip_subcommand() {
         res
     );
 }
+
+#[test]
+fn default_subcommand() {
+    #[derive(Debug, PartialEq, StructOpt)]
+    struct Opt {
+        #[structopt(short, long)]
+        verbose: bool,
+        #[structopt(subcommand)]
+        sub: Subcommands,
+    }
+
+    #[derive(Debug, PartialEq, StructOpt)]
+    enum Subcommands {
+        #[structopt(default_subcommand)]
+        Run {
+            #[structopt(long)]
+            release: bool,
+        },
+        Build,
+    }
+
+    assert_eq!(
+        Opt::from_iter(&["test"]),
+        Opt {
+            verbose: false,
+            sub: Subcommands::Run { release: false }
+        }
+    );
+
+    assert_eq!(
+        Opt::from_iter(&["test", "-v", "--release"]),
+        Opt {
+            verbose: true,
+            sub: Subcommands::Run { release: true }
+        }
+    );
+
+    assert_eq!(
+        Opt::from_iter(&["test", "run", "--release"]),
+        Opt {
+            verbose: false,
+            sub: Subcommands::Run { release: true }
+        }
+    );
+
+    assert_eq!(
+        Opt::from_iter(&["test", "build"]),
+        Opt {
+            verbose: false,
+            sub: Subcommands::Build
+        }
+    );
+}
+
+#[test]
+fn default_subcommand_required_args() {
+    #[derive(Debug, PartialEq, StructOpt)]
+    enum Opt {
+        #[structopt(default_subcommand)]
+        Open(Open),
+        List,
+    }
+
+    #[derive(Debug, PartialEq, StructOpt)]
+    struct Open {
+        file: String,
+    }
+
+    assert_eq!(
+        Opt::from_iter(&["test", "foo.txt"]),
+        Opt::Open(Open {
+            file: "foo.txt".into()
+        })
+    );
+    assert_eq!(
+        Opt::from_iter(&["test", "open", "foo.txt"]),
+        Opt::Open(Open {
+            file: "foo.txt".into()
+        })
+    );
+    assert_eq!(Opt::from_iter(&["test", "list"]), Opt::List);
+    assert!(Opt::from_iter_safe(&["test"]).is_err());
+}
@@ -0,0 +1,96 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+mod utils;
+
+use structopt::StructOpt;
+use utils::*;
+
+#[test]
+fn tuple_struct_positional() {
+    #[derive(StructOpt, PartialEq, Debug)]
+    struct Opt(
+        /// Source file
+        String,
+        #[structopt(name = "DEST")] String,
+        #[structopt(short, long)] Option<u32>,
+    );
+
+    assert_eq!(
+        Opt("a".into(), "b".into(), None),
+        Opt::from_iter(&["test", "a", "b"])
+    );
+    assert_eq!(
+        Opt("a".into(), "b".into(), Some(3)),
+        Opt::from_iter(&["test", "a", "-a", "3", "b"])
+    );
+    assert!(Opt::from_iter_safe(&["test", "a"]).is_err());
+
+    let help = get_long_help::<Opt>();
+    assert!(help.contains("<arg0> <DEST>"));
+    assert!(help.contains("Source file"));
+}
+
+#[test]
+fn single_field_tuple_struct_positional() {
+    #[derive(StructOpt, PartialEq, Debug)]
+    struct Opt(u32);
+
+    assert_eq!(Opt(42), Opt::from_iter(&["test", "42"]));
+    assert!(Opt::from_iter_safe(&["test"]).is_err());
+    assert!(get_help::<Opt>().contains("<arg0>"));
+}
+
+#[test]
+fn newtype_struct_delegates() {
+    #[derive(StructOpt, PartialEq, Debug)]
+    #[structopt(name = "inner")]
+    struct Inner {
+        #[structopt(short)]
+        verbose: bool,
+        file: String,
+    }
+
+    #[derive(StructOpt, PartialEq, Debug)]
+    struct Cli(#[structopt(flatten)] Inner);
+
+    assert_eq!(
+        Cli(Inner {
+            verbose: true,
+            file: "f".into()
+        }),
+        Cli::from_iter(&["test", "-v", "f"])
+    );
+    assert_eq!(Cli::clap().get_name(), "inner");
+}
+
+#[test]
+fn newtype_struct_wraps_subcommands() {
+    #[derive(StructOpt, PartialEq, Debug)]
+    enum Command {
+        Add { name: String },
+        Remove,
+    }
+
+    #[derive(StructOpt, PartialEq, Debug)]
+    struct Cmd(#[structopt(flatten)] Command);
+
+    #[derive(StructOpt, PartialEq, Debug)]
+    struct Opt {
+        #[structopt(subcommand)]
+        cmd: Cmd,
+    }
+
+    assert_eq!(
+        Opt {
+            cmd: Cmd(Command::Add { name: "x".into() })
+        },
+        Opt::from_iter(&["test", "add", "x"])
+    );
+    assert_eq!(Cmd(Command::Remove), Cmd::from_iter(&["test", "remove"]));
+}
@@ -0,0 +1,28 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::StructOpt;
+
+#[derive(Debug)]
+struct Level;
+
+#[derive(Debug)]
+struct Input;
+
+#[derive(StructOpt, Debug)]
+struct Opt {
+    #[structopt(long)]
+    level: Option<Level>,
+    #[structopt(parse(from_os_str))]
+    input: Vec<Input>,
+}
+
+fn main() {
+    let opt = Opt::from_args();
+    println!("{:?}", opt);
+}
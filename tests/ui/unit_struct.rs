@@ -10,7 +10,7 @@ use structopt::StructOpt;
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "basic")]
-struct Opt(u32);
+struct Opt;
 
 fn main() {
     let opt = Opt::from_args();
@@ -0,0 +1,293 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Common {
+    #[structopt(short, long)]
+    verbose: bool,
+    #[structopt(long, default_value = "info")]
+    log: String,
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Opt {
+    #[structopt(short, long)]
+    name: Option<String>,
+    #[structopt(long, default_value = "4")]
+    jobs: u32,
+    #[structopt(short, long, parse(from_occurrences))]
+    quiet: u8,
+    #[structopt(long)]
+    tag: Vec<String>,
+    #[structopt(flatten)]
+    common: Common,
+    #[structopt(skip = 42u32)]
+    skipped: u32,
+    #[structopt(subcommand)]
+    cmd: Option<Cmd>,
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+enum Cmd {
+    Build {
+        #[structopt(long)]
+        release: bool,
+        #[structopt(long, default_value = "x86")]
+        target: String,
+    },
+    Test(TestArgs),
+    Clean,
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct TestArgs {
+    #[structopt(long)]
+    filter: Option<String>,
+    #[structopt(long)]
+    nocapture: bool,
+}
+
+fn opt() -> Opt {
+    Opt::from_iter(&[
+        "test",
+        "-n",
+        "foo",
+        "--jobs",
+        "8",
+        "-qq",
+        "--tag",
+        "a",
+        "-v",
+        "build",
+        "--release",
+        "--target",
+        "arm",
+    ])
+}
+
+#[test]
+fn update_nothing() {
+    let mut opt = opt();
+    opt.skipped = 1;
+    opt.update_from_iter(&["test"]);
+    assert_eq!(
+        Opt {
+            skipped: 1,
+            ..self::opt()
+        },
+        opt
+    );
+}
+
+#[test]
+fn update_present_args() {
+    let mut opt = opt();
+    opt.update_from_iter(&["test", "--tag", "b", "--tag", "c", "--log", "debug", "-q"]);
+    assert_eq!(Some("foo".to_string()), opt.name);
+    assert_eq!(8, opt.jobs);
+    assert_eq!(1, opt.quiet);
+    assert_eq!(vec!["b", "c"], opt.tag);
+    assert_eq!(
+        Common {
+            verbose: true,
+            log: "debug".to_string()
+        },
+        opt.common
+    );
+    assert_eq!(42, opt.skipped);
+}
+
+#[test]
+fn update_active_subcommand() {
+    let mut opt = opt();
+    opt.update_from_iter(&["test", "build"]);
+    assert_eq!(
+        Some(Cmd::Build {
+            release: true,
+            target: "arm".to_string()
+        }),
+        opt.cmd
+    );
+
+    opt.update_from_iter(&["test", "build", "--target", "mips"]);
+    assert_eq!(
+        Some(Cmd::Build {
+            release: true,
+            target: "mips".to_string()
+        }),
+        opt.cmd
+    );
+}
+
+#[test]
+fn update_other_subcommand() {
+    let mut opt = opt();
+    opt.update_from_iter(&["test", "test", "--filter", "foo"]);
+    assert_eq!(
+        Some(Cmd::Test(TestArgs {
+            filter: Some("foo".to_string()),
+            nocapture: false,
+        })),
+        opt.cmd
+    );
+
+    opt.update_from_iter(&["test", "test", "--nocapture"]);
+    assert_eq!(
+        Some(Cmd::Test(TestArgs {
+            filter: Some("foo".to_string()),
+            nocapture: true,
+        })),
+        opt.cmd
+    );
+
+    opt.update_from_iter(&["test", "clean"]);
+    assert_eq!(Some(Cmd::Clean), opt.cmd);
+}
+
+#[test]
+fn update_missing_subcommand() {
+    let mut opt = Opt::from_iter(&["test"]);
+    assert_eq!(None, opt.cmd);
+    opt.update_from_iter(&["test", "clean"]);
+    assert_eq!(Some(Cmd::Clean), opt.cmd);
+}
+
+#[test]
+fn update_enum() {
+    let mut cmd = Cmd::from_iter(&["test", "build", "--target", "arm"]);
+    cmd.update_from_iter(&["test", "build", "--release"]);
+    assert_eq!(
+        Cmd::Build {
+            release: true,
+            target: "arm".to_string()
+        },
+        cmd
+    );
+}
+
+#[test]
+fn update_error_leaves_value() {
+    let mut opt = opt();
+    assert!(opt
+        .update_from_iter_safe(&["test", "--jobs", "many"])
+        .is_err());
+    assert_eq!(self::opt(), opt);
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+enum WithDefault {
+    #[structopt(default_subcommand)]
+    Run {
+        #[structopt(long)]
+        fast: bool,
+        #[structopt(long)]
+        name: Option<String>,
+    },
+    Stop,
+}
+
+#[test]
+fn update_default_subcommand() {
+    let mut cmd = WithDefault::from_iter(&["test", "--fast"]);
+    cmd.update_from_iter(&["test", "--name", "foo"]);
+    assert_eq!(
+        WithDefault::Run {
+            fast: true,
+            name: Some("foo".to_string())
+        },
+        cmd
+    );
+
+    cmd.update_from_iter(&["test", "stop"]);
+    assert_eq!(WithDefault::Stop, cmd);
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Server {
+    #[structopt(long)]
+    host: String,
+    #[structopt(long)]
+    verbose: bool,
+    #[structopt(flatten)]
+    port: Port,
+    file: String,
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Port {
+    #[structopt(long)]
+    port: u16,
+}
+
+#[test]
+fn update_without_required_arguments() {
+    let mut server = Server::from_iter(&["test", "--host", "a", "--port", "80", "f"]);
+    server.update_from_iter(&["test", "--verbose"]);
+    assert_eq!(
+        Server {
+            host: "a".into(),
+            verbose: true,
+            port: Port { port: 80 },
+            file: "f".into(),
+        },
+        server
+    );
+
+    server.update_from_iter(&["test", "--port", "8080", "g"]);
+    assert_eq!(8080, server.port.port);
+    assert_eq!("g", server.file);
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Session {
+    #[structopt(long)]
+    verbose: bool,
+    #[structopt(subcommand)]
+    cmd: Action,
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+enum Action {
+    Connect {
+        #[structopt(long)]
+        host: String,
+        #[structopt(long)]
+        retries: Option<u32>,
+    },
+    Quit,
+}
+
+#[test]
+fn update_without_required_subcommand() {
+    let mut session = Session::from_iter(&["test", "connect", "--host", "a"]);
+    session.update_from_iter(&["test", "--verbose"]);
+    assert_eq!(
+        Session {
+            verbose: true,
+            cmd: Action::Connect {
+                host: "a".into(),
+                retries: None
+            },
+        },
+        session
+    );
+
+    // the arguments of a subcommand are still required, as it may replace
+    // the active one
+    let err = session
+        .update_from_iter_safe(&["test", "connect", "--retries", "3"])
+        .unwrap_err();
+    assert_eq!(
+        structopt::clap::ErrorKind::MissingRequiredArgument,
+        err.kind
+    );
+    session.update_from_iter(&["test", "quit"]);
+    assert_eq!(Action::Quit, session.cmd);
+}
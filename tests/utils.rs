@@ -43,3 +43,46 @@ pub fn get_subcommand_long_help<T: StructOpt>(subcmd: &str) -> String {
 
     output
 }
+
+/// Runs the example `name` with `args`, building it first.
+pub fn run_example(name: &str, args: &[&str]) -> std::process::Output {
+    let build = std::process::Command::new(env!("CARGO"))
+        .args(&["build", "--quiet", "--example", name])
+        .output()
+        .unwrap();
+    assert!(
+        build.status.success(),
+        "failed to build example `{}`:\n{}",
+        name,
+        String::from_utf8_lossy(&build.stderr)
+    );
+
+    let mut path = std::env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    path.push("examples");
+    path.push(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+
+    std::process::Command::new(path)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Runs `f` in a new process running only the test `name` of the current test
+/// binary, and returns the output of that process. Only the first call of a
+/// test runs in the child.
+pub fn run_in_child(name: &str, f: impl FnOnce()) -> std::process::Output {
+    if std::env::var_os("STRUCTOPT_TEST_CHILD").is_some() {
+        f();
+        std::process::exit(0);
+    }
+
+    std::process::Command::new(std::env::current_exe().unwrap())
+        .args(&[name, "--exact", "--nocapture", "--quiet"])
+        .env("STRUCTOPT_TEST_CHILD", "1")
+        .output()
+        .unwrap()
+}
@@ -0,0 +1,163 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::clap::ErrorKind;
+use structopt::StructOpt;
+
+fn ordered(range: &Range) -> Result<(), String> {
+    if range.start < range.end {
+        Ok(())
+    } else {
+        Err(format!(
+            "--start ({}) must be less than --end ({})",
+            range.start, range.end
+        ))
+    }
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+#[structopt(validate = ordered)]
+struct Range {
+    #[structopt(long)]
+    start: u32,
+    #[structopt(long)]
+    end: u32,
+}
+
+#[test]
+fn struct_validation() {
+    assert_eq!(
+        Range { start: 1, end: 2 },
+        Range::from_iter_safe(&["test", "--start", "1", "--end", "2"]).unwrap()
+    );
+
+    let err = Range::from_iter_safe(&["test", "--start", "3", "--end", "2"]).unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+    assert!(err
+        .message
+        .contains("--start (3) must be less than --end (2)"));
+}
+
+#[test]
+fn closure_and_validate_method() {
+    #[derive(StructOpt, Debug, PartialEq)]
+    #[structopt(validate = |opt: &Opt| if opt.names.is_empty() { Err("no name".to_string()) } else { Ok(()) })]
+    struct Opt {
+        names: Vec<String>,
+    }
+
+    assert!(Opt::from_iter_safe(&["test", "a"]).is_ok());
+    let err = Opt::from_iter_safe(&["test"]).unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+
+    let opt = Opt { names: vec![] };
+    assert_eq!(ErrorKind::ValueValidation, opt.validate().unwrap_err().kind);
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+struct Opt {
+    #[structopt(flatten)]
+    range: Range,
+    #[structopt(subcommand)]
+    cmd: Option<Cmd>,
+}
+
+#[derive(StructOpt, Debug, PartialEq)]
+enum Cmd {
+    #[structopt(validate = Cmd::check_jobs)]
+    Build {
+        #[structopt(long, default_value = "1")]
+        jobs: u32,
+        #[structopt(long)]
+        max_jobs: Option<u32>,
+    },
+    Show(Range),
+    Quit,
+}
+
+impl Cmd {
+    fn check_jobs(&self) -> Result<(), String> {
+        match self {
+            Cmd::Build {
+                jobs,
+                max_jobs: Some(max),
+            } if jobs > max => Err("--jobs can't be more than --max-jobs".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn nested_validation() {
+    let err = Opt::from_iter_safe(&["test", "--start", "3", "--end", "2"]).unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+
+    let err = Opt::from_iter_safe(&[
+        "test",
+        "--start",
+        "1",
+        "--end",
+        "2",
+        "build",
+        "--jobs",
+        "4",
+        "--max-jobs",
+        "2",
+    ])
+    .unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+    assert!(err.message.contains("--jobs can't be more than --max-jobs"));
+
+    let err = Opt::from_iter_safe(&[
+        "test", "--start", "1", "--end", "2", "show", "--start", "2", "--end", "1",
+    ])
+    .unwrap_err();
+    assert!(err
+        .message
+        .contains("--start (2) must be less than --end (1)"));
+
+    assert_eq!(
+        Opt {
+            range: Range { start: 1, end: 2 },
+            cmd: Some(Cmd::Build {
+                jobs: 2,
+                max_jobs: Some(4)
+            }),
+        },
+        Opt::from_iter_safe(&[
+            "test",
+            "--start",
+            "1",
+            "--end",
+            "2",
+            "build",
+            "--jobs",
+            "2",
+            "--max-jobs",
+            "4",
+        ])
+        .unwrap()
+    );
+}
+
+#[test]
+fn other_constructors() {
+    let err = Range::from_line_safe("test --start 2 --end 2").unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+
+    let err = Range::from_map(vec![("start", 5), ("end", 1)]).unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+
+    let mut range = Range { start: 1, end: 5 };
+    let err = range
+        .update_from_iter_safe(&["test", "--start", "6", "--end", "5"])
+        .unwrap_err();
+    assert_eq!(ErrorKind::ValueValidation, err.kind);
+    // the struct is checked once updated
+    assert_eq!(Range { start: 6, end: 5 }, range);
+}
@@ -0,0 +1,277 @@
+// Copyright 2018 Guillaume Pinot (@TeXitoi) <texitoi@texitoi.eu>
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use structopt::clap::ErrorKind;
+use structopt::{ErrorReport, ParseContext, StructOpt, ValueParser};
+
+use std::ffi::{OsStr, OsString};
+use std::num::ParseIntError;
+
+struct Radix(u32);
+
+impl ValueParser<str> for Radix {
+    type Value = u64;
+    type Error = ParseIntError;
+
+    fn parse_value(&self, input: &str) -> Result<u64, ParseIntError> {
+        u64::from_str_radix(input, self.0)
+    }
+}
+
+struct NonEmpty;
+
+impl ValueParser<OsStr> for NonEmpty {
+    type Value = String;
+    type Error = OsString;
+
+    fn parse_value(&self, input: &OsStr) -> Result<String, OsString> {
+        if input.is_empty() {
+            Err("must not be empty".into())
+        } else {
+            Ok(input.to_string_lossy().into_owned())
+        }
+    }
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+struct ExprOpt {
+    #[structopt(long, parse(try_from_str = |s| u8::from_str_radix(s, 16)))]
+    hex: Option<u8>,
+
+    #[structopt(long, parse(from_str = |s: &str| s.to_uppercase()))]
+    upper: Option<String>,
+
+    #[structopt(long, parse(try_from_str = Radix(2)))]
+    bits: Vec<u64>,
+
+    #[structopt(long, parse(try_from_os_str = &NonEmpty))]
+    label: Option<String>,
+}
+
+#[test]
+fn closures_and_parser_objects() {
+    assert_eq!(
+        ExprOpt {
+            hex: Some(0xff),
+            upper: Some("ABC".into()),
+            bits: vec![5, 2],
+            label: Some("x".into()),
+        },
+        ExprOpt::from_iter(&[
+            "test", "--hex", "ff", "--upper", "abc", "--bits", "101", "--bits", "10", "--label",
+            "x",
+        ])
+    );
+}
+
+#[test]
+fn parser_errors() {
+    let err = ExprOpt::from_iter_safe(&["test", "--hex", "fff"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+    assert!(err.message.contains("number too large"), "{}", err.message);
+
+    let err = ExprOpt::from_iter_safe(&["test", "--bits", "12"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+
+    let err = ExprOpt::from_iter_safe(&["test", "--label", ""]).unwrap_err();
+    assert!(err.message.contains("must not be empty"), "{}", err.message);
+}
+
+fn scaled(s: &str, context: &ParseContext<'_>) -> Result<u64, String> {
+    let value: u64 = s.parse().map_err(|e| format!("{}", e))?;
+    let scale = match context.value_of("unit") {
+        Some("k") => 1000,
+        Some("m") => 1_000_000,
+        Some(unit) => return Err(format!("unknown unit `{}`", unit)),
+        None => 1,
+    };
+    Ok(value * scale)
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+struct ContextOpt {
+    #[structopt(long, parse(try_from_str = scaled, context))]
+    size: u64,
+
+    #[structopt(long)]
+    unit: Option<String>,
+
+    #[structopt(
+        long,
+        default_value = "1",
+        range = 1..=10,
+        parse(try_from_str = |s: &str, ctx: &ParseContext<'_>| {
+            s.parse::<u32>().map(|n| n * ctx.get::<u32>("factor").unwrap_or(1))
+        }, context)
+    )]
+    count: u32,
+
+    #[structopt(long)]
+    factor: Option<u32>,
+}
+
+#[test]
+fn context_parsers() {
+    assert_eq!(
+        ContextOpt {
+            size: 3000,
+            unit: Some("k".into()),
+            count: 1,
+            factor: None,
+        },
+        ContextOpt::from_iter(&["test", "--size", "3", "--unit", "k"])
+    );
+    assert_eq!(
+        ContextOpt {
+            size: 3,
+            unit: None,
+            count: 6,
+            factor: Some(2),
+        },
+        ContextOpt::from_iter(&["test", "--factor", "2", "--count", "3", "--size", "3"])
+    );
+}
+
+#[test]
+fn context_parser_errors() {
+    let err = ContextOpt::from_iter_safe(&["test", "--size", "3", "--unit", "g"]).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+    assert!(err.message.contains("unknown unit `g`"), "{}", err.message);
+    let report = ErrorReport::new(&err);
+    assert_eq!(Some("size"), report.argument.as_deref());
+    assert_eq!(Some("3"), report.value.as_deref());
+
+    let err = ContextOpt::from_iter_safe(&["test", "--size", "1", "--count", "6", "--factor", "2"])
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+    assert!(
+        err.message.contains("6 is not in 1..=10"),
+        "{}",
+        err.message
+    );
+}
+
+#[test]
+fn context_parser_errors_on_update() {
+    let mut opt = ContextOpt::from_iter(&["test", "--size", "3"]);
+    let err = opt
+        .update_from_iter_safe(&["test", "--size", "3", "--unit", "g"])
+        .unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+    assert_eq!(opt.size, 3);
+}
+
+#[test]
+fn context_parser_errors_from_clap() {
+    let matches = ContextOpt::clap().get_matches_from(&["test", "--size", "x"]);
+    let err = ContextOpt::check_matches(&matches).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+    let err = ContextOpt::try_from_clap(&matches).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::ValueValidation);
+
+    let matches = ContextOpt::clap().get_matches_from(&["test", "--size", "3"]);
+    assert_eq!(3, ContextOpt::try_from_clap(&matches).unwrap().size);
+}
+
+#[test]
+#[should_panic(expected = "the matches must be accepted by `StructOpt::check_matches`")]
+fn from_clap_requires_check_matches() {
+    let matches = ContextOpt::clap().get_matches_from(&["test", "--size", "x"]);
+    ContextOpt::from_clap(&matches);
+}
+
+#[test]
+fn context_get_with_parser() {
+    #[derive(StructOpt, PartialEq, Debug)]
+    struct Opt {
+        #[structopt(long, parse(try_from_str = |s| u32::from_str_radix(s, 16)))]
+        mask: Option<u32>,
+        #[structopt(long, parse(try_from_str = |s: &str, ctx: &ParseContext<'_>| {
+            let mask = ctx.get_with("mask", |s: &str| u32::from_str_radix(s, 16));
+            s.parse::<u32>().map(|n| n & mask.unwrap_or(!0))
+        }, context))]
+        value: u32,
+    }
+
+    let opt = Opt::from_iter(&["test", "--mask", "f0", "--value", "255"]);
+    assert_eq!(opt.value, 0xf0);
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+struct Flattened {
+    #[structopt(long, parse(try_from_str = scaled, context))]
+    size: Option<u64>,
+
+    #[structopt(long)]
+    unit: Option<String>,
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+enum Cmd {
+    Alloc {
+        #[structopt(flatten)]
+        flattened: Flattened,
+    },
+    Free(Flattened),
+}
+
+#[derive(StructOpt, PartialEq, Debug)]
+struct Opt {
+    #[structopt(subcommand)]
+    cmd: Cmd,
+}
+
+#[test]
+fn context_parsers_in_subcommands() {
+    assert_eq!(
+        Opt {
+            cmd: Cmd::Alloc {
+                flattened: Flattened {
+                    size: Some(2_000_000),
+                    unit: Some("m".into()),
+                },
+            },
+        },
+        Opt::from_iter(&["test", "alloc", "--unit", "m", "--size", "2"])
+    );
+
+    let err = Opt::from_iter_safe(&["test", "alloc", "--unit", "g", "--size", "2"]).unwrap_err();
+    assert!(err.message.contains("unknown unit `g`"), "{}", err.message);
+
+    let err = Opt::from_iter_safe(&["test", "free", "--unit", "g", "--size", "2"]).unwrap_err();
+    assert!(err.message.contains("unknown unit `g`"), "{}", err.message);
+}
+
+#[test]
+fn context_parsers_in_default_subcommand() {
+    #[derive(StructOpt, PartialEq, Debug)]
+    enum Cmd {
+        #[structopt(default_subcommand)]
+        Alloc(Flattened),
+        Free,
+    }
+
+    #[derive(StructOpt, PartialEq, Debug)]
+    struct Opt {
+        #[structopt(subcommand)]
+        cmd: Cmd,
+    }
+
+    assert_eq!(
+        Opt {
+            cmd: Cmd::Alloc(Flattened {
+                size: Some(2000),
+                unit: Some("k".into()),
+            }),
+        },
+        Opt::from_iter(&["test", "--unit", "k", "--size", "2"])
+    );
+
+    let err = Opt::from_iter_safe(&["test", "--unit", "g", "--size", "2"]).unwrap_err();
+    assert!(err.message.contains("unknown unit `g`"), "{}", err.message);
+}